@@ -0,0 +1,154 @@
+//! Payloads of the UBX-CFG messages used to configure the receiver.
+
+use core::fmt;
+
+use crate::nmea::GPS_Statement;
+
+pub const RATE: u8 = 0x08;
+pub const PRT: u8 = 0x00;
+pub const MSG: u8 = 0x01;
+pub const NAV5: u8 = 0x24;
+pub const CFG: u8 = 0x09;
+pub const RST: u8 = 0x04;
+
+/// UBX class of the standard NMEA messages in CFG-MSG.
+pub const NMEA_CLASS: u8 = 0xF0;
+
+/// Attempts made by `NEO6::configure` before giving up.
+pub const RETRIES: u8 = 3;
+
+/// Factory default baud rate of the NEO-6 UART.
+pub const DEFAULT_BAUD_RATE: u32 = 9600;
+
+/// Rates tried by `NEO6::probe_baud_rate`, most likely first.
+pub const BAUD_RATES: [u32; 6] = [9600, 115_200, 38_400, 57_600, 19_200, 4800];
+
+/// Devices CFG-CFG saves the configuration to, can be or-ed together.
+pub mod device {
+    pub const BBR: u8 = 0x01;
+    pub const FLASH: u8 = 0x02;
+    pub const EEPROM: u8 = 0x04;
+}
+
+/// Why a configuration command failed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CfgError<E> {
+    /// The receiver rejected the message with ACK-NAK.
+    Nak,
+    /// No ACK after all retries.
+    Timeout,
+    /// The driver has no command channel to the module.
+    NoChannel,
+    /// Writing to the serial port failed.
+    Serial(E),
+}
+
+impl<E> fmt::Display for CfgError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let text = match self {
+            CfgError::Nak => "rejected by receiver",
+            CfgError::Timeout => "no acknowledge",
+            CfgError::NoChannel => "no command channel",
+            CfgError::Serial(_) => "serial error",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Why baud rate negotiation failed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BaudError<E> {
+    /// No valid sentence or frame at any rate; the MCU is left at
+    /// `DEFAULT_BAUD_RATE`.
+    NotDetected,
+    /// Sending CFG-PRT failed.
+    Command(CfgError<E>),
+}
+
+/// Navigation data CFG-RST clears before restarting.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StartMode {
+    /// Keep everything.
+    Hot = 0x0000,
+    /// Clear the ephemeris.
+    Warm = 0x0001,
+    /// Clear all battery-backed navigation data.
+    Cold = 0xFFFF,
+}
+
+/// Platform model of CFG-NAV5, trading filtering against allowed dynamics.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DynamicModel {
+    Portable = 0,
+    Stationary = 2,
+    Pedestrian = 3,
+    Automotive = 4,
+    Sea = 5,
+    /// Airborne with < 1g acceleration
+    Airborne1g = 6,
+    /// Airborne with < 2g acceleration
+    Airborne2g = 7,
+    /// Airborne with < 4g acceleration
+    Airborne4g = 8,
+}
+
+impl GPS_Statement {
+    /// Message id of the sentence in the NMEA class of CFG-MSG.
+    pub fn ubx_id(&self) -> u8 {
+        match self {
+            GPS_Statement::GGA => 0x00,
+            GPS_Statement::GLL => 0x01,
+            GPS_Statement::GSA => 0x02,
+            GPS_Statement::GSV => 0x03,
+            GPS_Statement::RMC => 0x04,
+            GPS_Statement::VTG => 0x05,
+            GPS_Statement::ZDA => 0x08,
+        }
+    }
+}
+
+/// CFG-RATE: measurement period in ms, aligned to GPS time.
+pub fn rate(period_ms: u16) -> [u8; 6] {
+    let period = period_ms.to_le_bytes();
+    [period[0], period[1], 0x01, 0x00, 0x01, 0x00]
+}
+
+/// CFG-PRT for UART1: 8N1 at `baud_rate`, UBX and NMEA in both directions.
+pub fn prt_uart(baud_rate: u32) -> [u8; 20] {
+    let mut payload = [0u8; 20];
+    payload[0] = 0x01;
+    payload[4..8].copy_from_slice(&0x0000_08D0u32.to_le_bytes());
+    payload[8..12].copy_from_slice(&baud_rate.to_le_bytes());
+    payload[12..14].copy_from_slice(&0x0003u16.to_le_bytes());
+    payload[14..16].copy_from_slice(&0x0003u16.to_le_bytes());
+    payload
+}
+
+/// CFG-MSG: output rate of a message on the current port, in navigation
+/// epochs; 0 disables it.
+pub fn msg_rate(class: u8, id: u8, rate: u8) -> [u8; 3] {
+    [class, id, rate]
+}
+
+/// CFG-NAV5 changing only the dynamic platform model.
+pub fn nav5(model: DynamicModel) -> [u8; 36] {
+    let mut payload = [0u8; 36];
+    payload[0] = 0x01;
+    payload[2] = model as u8;
+    payload
+}
+
+/// CFG-RST: controlled software reset. The receiver restarts with its saved
+/// configuration and does not acknowledge the message.
+pub fn rst(start: StartMode) -> [u8; 4] {
+    let mask = (start as u16).to_le_bytes();
+    [mask[0], mask[1], 0x01, 0x00]
+}
+
+/// CFG-CFG saving the current configuration to `devices`, see `device`.
+pub fn save(devices: u8) -> [u8; 13] {
+    let mut payload = [0u8; 13];
+    payload[4..8].copy_from_slice(&0x0000_061Fu32.to_le_bytes());
+    payload[12] = devices;
+    payload
+}
@@ -0,0 +1,472 @@
+use crate::decimal::Decimal;
+use crate::time::UtcDateTime;
+use crate::types::{GPSTime, GPSDate, Position, Coordinate, FixType, FixMode, PositioningMode};
+use crate::nmea::{RMC, GGA, GSA, VTG, GLL, ZDA, TalkerId};
+use crate::sky::SkyView;
+use crate::ubx::{NavPosllh, NavStatus, NavSol, NavTimeUtc, NavPvt, millis};
+
+/// Default for `GPS_Data::set_max_age`, in seconds.
+pub const DEFAULT_MAX_AGE: u32 = 2;
+/// Default for `GPS_Data::set_max_silence`, in ticks: 2 s at 1 kHz.
+pub const DEFAULT_MAX_SILENCE: u32 = 2000;
+
+/// Fields of `GPS_Data` updated together.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Group {
+    /// Fix type and mode, RMC status.
+    Fix = 0,
+    Position = 1,
+    /// Speed and course.
+    Velocity = 2,
+    /// DOP values and satellites used, from GSA.
+    Dop = 3,
+}
+
+/// Navigation epoch a group was last updated in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stamp {
+    /// Count of distinct UTC times received so far.
+    pub epoch: u32,
+    /// UTC time of the epoch.
+    pub time: GPSTime,
+}
+
+/// Whether the data describes the receiver's current position.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Validity {
+    /// The receiver reports no fix, or never did.
+    NoFix,
+    /// The latest fix or position is older than the maximum age, or the
+    /// receiver has been silent for too long.
+    Stale,
+    /// Fresh fix, but 2D or with a too high HDOP.
+    Degraded,
+    /// Fresh 3D fix with HDOP below the requested limit.
+    Fresh,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GPS_Data {
+
+    // from RMC
+    valid: bool,
+    date: GPSDate,
+    speed: Decimal,
+    course: Decimal,
+    magnetic_variation: Option<Decimal>,
+    mode: Option<PositioningMode>,
+    // from GGA
+    position: Position,
+    satellites_used: u8,
+    fix: FixType,
+    time: GPSTime,
+    // from GSA
+    hdop: Decimal,
+    vdop: Decimal,
+    pdop: Decimal,
+    fix_mode: FixMode,
+    satellite_ids: [u8; 12],
+    // epoch the satellite IDs were collected in
+    gsa_epoch: u32,
+    gsa_seen: bool,
+    // from GSV
+    sky_view: SkyView,
+    // from VTG
+    speed_kmh: Decimal,
+    // from ZDA
+    year: Option<u16>,
+    local_zone: (i8, u8),
+    // talker of the last RMC, GGA or GLL
+    talker: TalkerId,
+    // when each group was last updated
+    epoch: u32,
+    stamps: [Option<Stamp>; 4],
+    max_age: u32,
+    // caller's tick the latest UTC time was received at
+    received: Option<u32>,
+    max_silence: u32,
+}
+
+impl GPS_Data {
+    pub fn new() -> Self {
+        GPS_Data {
+            // from RMC
+            valid: false,
+            date: GPSDate::new(),
+            speed: Decimal::ZERO,
+            course: Decimal::ZERO,
+            magnetic_variation: None,
+            mode: None,
+            // from GGA
+            position: Position::new(),
+            satellites_used: 0,
+            fix: FixType::NoFix,
+            time: GPSTime::new(),
+            // from GSA
+            hdop: Decimal::ZERO,
+            vdop: Decimal::ZERO,
+            pdop: Decimal::ZERO,
+            fix_mode: FixMode::NoFix,
+            satellite_ids: [0u8; 12],
+            gsa_epoch: 0,
+            gsa_seen: false,
+            // from GSV
+            sky_view: SkyView::new(),
+            // from VTG
+            speed_kmh: Decimal::ZERO,
+            // from ZDA
+            year: None,
+            local_zone: (0, 0),
+            talker: TalkerId::GP,
+            epoch: 0,
+            stamps: [None; 4],
+            max_age: DEFAULT_MAX_AGE,
+            received: None,
+            max_silence: DEFAULT_MAX_SILENCE,
+        }
+    }
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+    /// Fresh fix of any quality, see `validity()`.
+    pub fn has_fix(&self) -> bool {
+        self.fix_state() == Validity::Degraded
+    }
+    /// Classifies the fix. The latest fix status and position must be at most
+    /// `max_age` seconds older than the latest UTC time received; `Fresh`
+    /// additionally needs a 3D fix and a current HDOP below `max_hdop`.
+    pub fn validity(&self, max_hdop: Decimal) -> Validity {
+        match self.fix_state() {
+            Validity::Degraded if self.fix_mode == FixMode::D3 && self.is_fresh(Group::Dop) && self.hdop < max_hdop => {
+                Validity::Fresh
+            },
+            state => state,
+        }
+    }
+    /// `has_fix()`, unless the data was received more than `max_silence`
+    /// ticks before `now`.
+    pub fn has_fix_at(&self, now: u32) -> bool {
+        self.has_fix() && !self.is_silent(now)
+    }
+    /// `validity()`, with a fix received more than `max_silence` ticks
+    /// before `now` taken as stale.
+    pub fn validity_at(&self, now: u32, max_hdop: Decimal) -> Validity {
+        match self.validity(max_hdop) {
+            Validity::Fresh | Validity::Degraded if self.is_silent(now) => Validity::Stale,
+            state => state,
+        }
+    }
+    // Fresh fix is reported as `Degraded`, leaving the quality to `validity()`.
+    fn fix_state(&self) -> Validity {
+        // the 2D/3D mode is only checked once a GSA has been received, so
+        // RMC alone is enough
+        let fix = self.valid && self.fix != FixType::NoFix && (!self.gsa_seen || self.fix_mode != FixMode::NoFix);
+        if !fix {
+            Validity::NoFix
+        } else if !self.is_fresh(Group::Fix) || !self.is_fresh(Group::Position) {
+            Validity::Stale
+        } else {
+            Validity::Degraded
+        }
+    }
+    /// Seconds a group is allowed to lag behind the latest UTC time.
+    pub fn set_max_age(&mut self, seconds: u32) {
+        self.max_age = seconds;
+    }
+    pub fn get_max_age(&self) -> u32 {
+        self.max_age
+    }
+    /// Ticks of the caller's monotonic clock the data may go without a new
+    /// UTC time before it is stale, see `validity_at()`.
+    pub fn set_max_silence(&mut self, ticks: u32) {
+        self.max_silence = ticks;
+    }
+    pub fn get_max_silence(&self) -> u32 {
+        self.max_silence
+    }
+    /// Records the tick the latest UTC time was received at.
+    pub fn set_received(&mut self, tick: u32) {
+        self.received = Some(tick);
+    }
+    pub fn get_received(&self) -> Option<u32> {
+        self.received
+    }
+    /// Ticks since the latest UTC time was received, `None` if that is not
+    /// known. The ticks wrap, as for the watchdog.
+    pub fn silence(&self, now: u32) -> Option<u32> {
+        self.received.map(|tick| now.wrapping_sub(tick))
+    }
+    fn is_silent(&self, now: u32) -> bool {
+        match self.silence(now) {
+            Some(silence) => silence > self.max_silence,
+            None => false,
+        }
+    }
+    /// Count of distinct UTC times received so far.
+    pub fn get_epoch(&self) -> u32 {
+        self.epoch
+    }
+    /// When `group` was last updated, `None` if never.
+    pub fn get_stamp(&self, group: Group) -> Option<Stamp> {
+        self.stamps[group as usize]
+    }
+    /// Seconds between the last update of `group` and the latest UTC time.
+    pub fn age(&self, group: Group) -> Option<u32> {
+        self.stamps[group as usize].map(|stamp| {
+            (self.time.seconds_of_day() + 86_400 - stamp.time.seconds_of_day()) % 86_400
+        })
+    }
+    fn is_fresh(&self, group: Group) -> bool {
+        match self.age(group) {
+            Some(age) => age <= self.max_age,
+            None => false,
+        }
+    }
+    pub fn get_position(&self) -> Position {
+        self.position
+    }
+    pub fn get_time(&self) -> GPSTime {
+        self.time
+    }
+    pub fn get_date(&self) -> GPSDate {
+        self.date
+    }
+    /// Date and time combined, `None` until a valid date has been received.
+    pub fn get_utc(&self) -> Option<UtcDateTime> {
+        UtcDateTime::from_gps(self.date, self.time, self.year)
+    }
+    pub fn satellites_no(&self) -> u8 {
+        self.satellites_used
+    }
+    pub fn get_fix_type(&self) -> FixType {
+        self.fix
+    }
+    pub fn get_fix_mode(&self) -> FixMode {
+        self.fix_mode
+    }
+    pub fn get_speed(&self) -> Decimal {
+        self.speed
+    }
+    pub fn get_speed_kmh(&self) -> Decimal {
+        self.speed_kmh
+    }
+    /// Four-digit year, only known once a ZDA has been received.
+    pub fn get_full_year(&self) -> Option<u16> {
+        self.year
+    }
+    /// Local zone offset `(hours, minutes)` from ZDA.
+    pub fn get_local_zone(&self) -> (i8, u8) {
+        self.local_zone
+    }
+    /// Talker of the latest position, `GN` for a multi-constellation solution.
+    pub fn get_talker(&self) -> TalkerId {
+        self.talker
+    }
+    pub fn set_talker(&mut self, talker: TalkerId) {
+        self.talker = talker;
+    }
+    pub fn get_course(&self) -> Decimal {
+        self.course
+    }
+    pub fn get_magnetic_variation(&self) -> Option<Decimal> {
+        self.magnetic_variation
+    }
+    pub fn get_mode(&self) -> Option<PositioningMode> {
+        self.mode
+    }
+    pub fn get_hdop(&self) -> Decimal {
+        self.hdop
+    }
+    pub fn get_vdop(&self) -> Decimal {
+        self.vdop
+    }
+    pub fn get_pdop(&self) -> Decimal {
+        self.pdop
+    }
+    pub fn get_satellite_ids(&self) -> [u8; 12] {
+        self.satellite_ids
+    }
+    /// Satellites in view with signal strength and whether they are used in the fix.
+    pub fn get_sky_view(&self) -> &SkyView {
+        &self.sky_view
+    }
+    /// Position, speed and course are kept from earlier epochs while the
+    /// receiver has no fix; so are they in the other updates.
+    pub fn update_rmc (&mut self, data: RMC) {
+        self.set_time(data.time);
+        self.valid = data.valid;
+        self.date = data.date;
+        self.magnetic_variation = data.magnetic_variation;
+        self.mode = data.mode;
+        self.fix = match data.mode {
+            Some(mode) => mode.fix_type(),
+            None if data.valid => FixType::GPSFix,
+            None => FixType::NoFix,
+        };
+        self.touch(Group::Fix);
+        if self.fix != FixType::NoFix {
+            self.position.latitude = data.latitude;
+            self.position.longitude = data.longitude;
+            self.touch(Group::Position);
+            self.speed = data.speed;
+            self.course = data.course;
+            self.touch(Group::Velocity);
+        }
+    }
+    pub fn update_gga (&mut self, data: GGA) {
+        self.set_time(data.time);
+        self.satellites_used = data.satellites_used;
+        self.fix = data.fix;
+        self.touch(Group::Fix);
+        if self.fix != FixType::NoFix {
+            self.position = data.position;
+            self.touch(Group::Position);
+        }
+    }
+    /// With several constellations the receiver sends one GSA per system in
+    /// each epoch; their satellite IDs are merged, up to 12.
+    pub fn update_gsa (&mut self, data: GSA) {
+        self.hdop = data.hdop;
+        self.vdop = data.vdop;
+        self.pdop = data.pdop;
+        self.fix_mode = data.fix;
+        // before any UTC time there are no epochs to merge within
+        if self.gsa_epoch != self.epoch || self.epoch == 0 {
+            self.satellite_ids = [0u8; 12];
+            self.gsa_epoch = self.epoch;
+        }
+        for &id in data.satellite_ids.iter().filter(|&&id| id != 0) {
+            if self.satellite_ids.contains(&id) {
+                continue;
+            }
+            if let Some(free) = self.satellite_ids.iter_mut().find(|slot| **slot == 0) {
+                *free = id;
+            }
+        }
+        self.gsa_seen = true;
+        self.sky_view.mark_used(&self.satellite_ids);
+        self.touch(Group::Fix);
+        self.touch(Group::Dop);
+    }
+    pub fn update_vtg (&mut self, data: VTG) {
+        if data.mode.is_some() {
+            self.mode = data.mode;
+        }
+        // without the NMEA 2.3 mode there is no telling, take it as valid
+        let fix = match data.mode {
+            Some(mode) => mode.fix_type() != FixType::NoFix,
+            None => true,
+        };
+        if fix {
+            self.course = data.course;
+            self.speed = data.speed_knots;
+            self.speed_kmh = data.speed_kmh;
+            self.touch(Group::Velocity);
+        }
+    }
+    pub fn update_gll (&mut self, data: GLL) {
+        self.set_time(data.time);
+        if data.mode.is_some() {
+            self.mode = data.mode;
+        }
+        if data.valid {
+            self.position.latitude = data.latitude;
+            self.position.longitude = data.longitude;
+            self.touch(Group::Position);
+        }
+    }
+    pub fn update_zda (&mut self, data: ZDA) {
+        self.set_time(data.time);
+        self.date = GPSDate {
+            day: data.day,
+            month: data.month,
+            year: (data.year % 100) as u8,
+        };
+        self.year = Some(data.year);
+        self.local_zone = (data.zone_hours, data.zone_minutes);
+    }
+    /// Merges a complete GSV sequence into the sky view, replacing the
+    /// previous satellites of the same talker.
+    pub fn update_sky_view (&mut self, view: SkyView) {
+        self.sky_view.merge(&view);
+        self.sky_view.mark_used(&self.satellite_ids);
+    }
+    pub fn update_nav_posllh (&mut self, data: NavPosllh) {
+        self.position.latitude = Coordinate::from_degrees_e7(data.lat);
+        self.position.longitude = Coordinate::from_degrees_e7(data.lon);
+        self.position.altitude = Decimal::new(data.height_msl, 3);
+        self.touch(Group::Position);
+    }
+    pub fn update_nav_status (&mut self, data: NavStatus) {
+        self.fix = data.fix.fix_type(data.flags);
+        self.fix_mode = data.fix.fix_mode();
+        self.valid = self.fix != FixType::NoFix;
+        self.gsa_seen = true;
+        self.touch(Group::Fix);
+    }
+    pub fn update_nav_sol (&mut self, data: NavSol) {
+        self.fix = data.fix.fix_type(data.flags);
+        self.fix_mode = data.fix.fix_mode();
+        self.valid = self.fix != FixType::NoFix;
+        self.gsa_seen = true;
+        self.touch(Group::Fix);
+        self.pdop = Decimal::new(data.pdop as i32, 2);
+        self.satellites_used = data.num_sv;
+    }
+    /// Ignored until the receiver flags the UTC time as valid.
+    pub fn update_nav_timeutc (&mut self, data: NavTimeUtc) {
+        if data.valid & 0x04 == 0 {
+            return;
+        }
+        let time = GPSTime { hour: data.hour, minute: data.min, second: data.sec, millisecond: millis(data.nano) };
+        self.set_utc(data.year, data.month, data.day, time);
+    }
+    pub fn update_nav_pvt (&mut self, data: NavPvt) {
+        if data.valid & 0x03 == 0x03 {
+            let time = GPSTime { hour: data.hour, minute: data.min, second: data.sec, millisecond: millis(data.nano) };
+            self.set_utc(data.year, data.month, data.day, time);
+        }
+        self.fix = data.fix.fix_type(data.flags);
+        self.fix_mode = data.fix.fix_mode();
+        self.valid = self.fix != FixType::NoFix;
+        self.gsa_seen = true;
+        self.satellites_used = data.num_sv;
+        self.pdop = Decimal::new(data.pdop as i32, 2);
+        self.touch(Group::Fix);
+        if !self.valid {
+            return;
+        }
+        self.position.latitude = Coordinate::from_degrees_e7(data.lat);
+        self.position.longitude = Coordinate::from_degrees_e7(data.lon);
+        self.position.altitude = Decimal::new(data.height_msl, 3);
+        self.touch(Group::Position);
+        // mm/s to 1e-3 knots and 1e-3 km/h
+        let speed = data.ground_speed as i64;
+        self.speed = Decimal::new((speed * 3600 / 1852) as i32, 3);
+        self.speed_kmh = Decimal::new((speed * 36 / 10) as i32, 3);
+        self.course = Decimal::new(data.heading, 5);
+        self.touch(Group::Velocity);
+    }
+    fn set_utc(&mut self, year: u16, month: u8, day: u8, time: GPSTime) {
+        self.set_time(time);
+        self.date = GPSDate { day, month, year: (year % 100) as u8 };
+        self.year = Some(year);
+    }
+    // a new UTC time starts a new epoch
+    fn set_time(&mut self, time: GPSTime) {
+        if self.epoch == 0 || time != self.time {
+            self.epoch = self.epoch.wrapping_add(1);
+        }
+        self.time = time;
+    }
+    // tags `group` with the current epoch
+    fn touch(&mut self, group: Group) {
+        self.stamps[group as usize] = Some(Stamp { epoch: self.epoch, time: self.time });
+    }
+}
+
+impl Default for GPS_Data {
+    fn default() -> Self {
+        GPS_Data::new()
+    }
+}
@@ -0,0 +1,258 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Add, Sub, Mul, Neg};
+use core::str::FromStr;
+
+use crate::error::NmeaError;
+
+/// Largest number of decimal places a `Decimal` can carry.
+pub const MAX_SCALE: u8 = 9;
+
+const POW10: [i64; 10] = [
+    1, 10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000, 100_000_000, 1_000_000_000,
+];
+
+/// Exact signed fixed-point number, `value / 10^scale`.
+///
+/// Parsed NMEA fields keep the number of decimal places the receiver sent,
+/// so `"12.05"` and `"12.50"` both print back exactly as received.
+/// Comparison is numeric: `12.5 == 12.50`, and `-0.0 == 0.0` though it
+/// prints with its sign.
+///
+/// When a result does not fit, the operators drop decimal places, rounding,
+/// and saturate only once there are none left; use the `checked_*` methods
+/// to detect overflow.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Decimal {
+    value: i32,
+    scale: u8,
+    // a zero value written with a minus sign
+    negative_zero: bool,
+}
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal { value: 0, scale: 0, negative_zero: false };
+
+    /// `value / 10^scale`, `scale` is clamped to `MAX_SCALE`.
+    pub const fn new(value: i32, scale: u8) -> Self {
+        Decimal {
+            value,
+            scale: if scale > MAX_SCALE { MAX_SCALE } else { scale },
+            negative_zero: false,
+        }
+    }
+
+    /// Parses `[-+]digits[.digits]` as found in NMEA fields. A decimal
+    /// point must be followed by at least one digit.
+    pub fn parse(field: &[u8]) -> Result<Self, NmeaError> {
+        let (negative, digits) = match field.split_first() {
+            Some((b'-', rest)) => (true, rest),
+            Some((b'+', rest)) => (false, rest),
+            _ => (false, field),
+        };
+        if digits.is_empty() {
+            return Err(NmeaError::MissingField);
+        }
+        let mut value = 0i64;
+        let mut scale = None;
+        let mut any_digit = false;
+        for c in digits {
+            match c {
+                b'0'..=b'9' => {
+                    any_digit = true;
+                    value = value * 10 + (c - b'0') as i64;
+                    if value > i32::MAX as i64 {
+                        return Err(NmeaError::BadDigit);
+                    }
+                    if let Some(s) = scale {
+                        if s == MAX_SCALE {
+                            return Err(NmeaError::BadDigit);
+                        }
+                        scale = Some(s + 1);
+                    }
+                },
+                b'.' if scale.is_none() => scale = Some(0),
+                _ => return Err(NmeaError::BadDigit),
+            }
+        }
+        if !any_digit || scale == Some(0) {
+            return Err(NmeaError::BadDigit);
+        }
+        let scale = scale.unwrap_or(0);
+        let value = if negative { -value } else { value };
+        Ok(Decimal { value: value as i32, scale, negative_zero: negative && value == 0 })
+    }
+
+    /// Unscaled integer value.
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+    /// Number of decimal places.
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+    pub fn is_negative(&self) -> bool {
+        self.value < 0
+    }
+    /// Integer part, truncated towards zero.
+    pub fn trunc(&self) -> i32 {
+        (self.value as i64 / POW10[self.scale as usize]) as i32
+    }
+    /// Same number with `scale` decimal places, rounding half away from zero
+    /// when places are dropped. `None` if it no longer fits.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let value = rescale(self.value as i64, self.scale, scale);
+        if value < i32::MIN as i64 || value > i32::MAX as i64 {
+            return None;
+        }
+        Some(Decimal { value: value as i32, scale, negative_zero: false })
+    }
+    pub fn to_f32(&self) -> f32 {
+        self.value as f32 / POW10[self.scale as usize] as f32
+    }
+    pub fn to_f64(&self) -> f64 {
+        self.value as f64 / POW10[self.scale as usize] as f64
+    }
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (sum, scale) = self.sum(other.value as i64, other.scale);
+        from_i64(sum, scale)
+    }
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (difference, scale) = self.sum(-(other.value as i64), other.scale);
+        from_i64(difference, scale)
+    }
+    /// Product, keeping at most `MAX_SCALE` decimal places.
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let (product, scale) = self.product(other);
+        from_i64(product, scale)
+    }
+    /// `None` for `i32::MIN`, which has no positive counterpart.
+    pub fn checked_neg(self) -> Option<Self> {
+        self.value.checked_neg().map(|value| Decimal {
+            value,
+            scale: self.scale,
+            negative_zero: value == 0 && !self.negative_zero,
+        })
+    }
+    fn sum(self, other: i64, other_scale: u8) -> (i64, u8) {
+        let scale = self.scale.max(other_scale);
+        (rescale(self.value as i64, self.scale, scale) + rescale(other, other_scale, scale), scale)
+    }
+    fn product(self, other: Self) -> (i64, u8) {
+        let product = self.value as i64 * other.value as i64;
+        let scale = self.scale + other.scale;
+        if scale > MAX_SCALE {
+            (rescale(product, scale, MAX_SCALE), MAX_SCALE)
+        } else {
+            (product, scale)
+        }
+    }
+}
+
+fn rescale(value: i64, from: u8, to: u8) -> i64 {
+    if to >= from {
+        value * POW10[(to - from) as usize]
+    } else {
+        let div = POW10[(from - to) as usize];
+        let half = div / 2 * value.signum();
+        (value + half) / div
+    }
+}
+
+fn from_i64(value: i64, scale: u8) -> Option<Decimal> {
+    if value < i32::MIN as i64 || value > i32::MAX as i64 {
+        return None;
+    }
+    Some(Decimal::new(value as i32, scale))
+}
+
+// drops decimal places until the value fits, clamps it only without any
+fn saturate(value: i64, scale: u8) -> Decimal {
+    for to in (1..=scale).rev() {
+        if let Some(decimal) = from_i64(rescale(value, scale, to), to) {
+            return decimal;
+        }
+    }
+    let integer = rescale(value, scale, 0).max(i32::MIN as i64).min(i32::MAX as i64);
+    Decimal::new(integer as i32, 0)
+}
+
+impl From<i32> for Decimal {
+    fn from(value: i32) -> Self {
+        Decimal::new(value, 0)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = NmeaError;
+    fn from_str(s: &str) -> Result<Self, NmeaError> {
+        Decimal::parse(s.as_bytes())
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+    fn add(self, other: Self) -> Self {
+        let (sum, scale) = self.sum(other.value as i64, other.scale);
+        saturate(sum, scale)
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+    fn sub(self, other: Self) -> Self {
+        let (difference, scale) = self.sum(-(other.value as i64), other.scale);
+        saturate(difference, scale)
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+    fn mul(self, other: Self) -> Self {
+        let (product, scale) = self.product(other);
+        saturate(product, scale)
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+    fn neg(self) -> Self {
+        self.checked_neg().unwrap_or_else(|| saturate(-(self.value as i64), self.scale))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        rescale(self.value as i64, self.scale, scale).cmp(&rescale(other.value as i64, other.scale, scale))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let sign = if self.value < 0 || self.negative_zero { "-" } else { "" };
+        let abs = (self.value as i64).abs();
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, abs);
+        }
+        let div = POW10[self.scale as usize];
+        write!(f, "{}{}.{:0width$}", sign, abs / div, abs % div, width = self.scale as usize)
+    }
+}
@@ -0,0 +1,146 @@
+//! Reception from a circular DMA buffer.
+//!
+//! The DMA channel writes the serial stream into a buffer over and over;
+//! the `CircularReader` copies out whatever the DMA wrote since the last
+//! read, in contiguous chunks ready for `NEO6::feed`. The buffer is only
+//! ever read through a raw pointer, as the DMA writes it concurrently.
+//!
+//! Reading at least twice per pass of the buffer means no byte is
+//! overwritten unread. To notice when that fails, report the channel's
+//! half-transfer and transfer-complete events: an event for a boundary the
+//! reader never reached means the DMA has lapped it.
+
+use core::ptr;
+
+use crate::error::LineError;
+use crate::queue::Producer;
+
+/// Read position in a circular DMA buffer.
+pub struct CircularReader {
+    buffer: *const u8,
+    len: usize,
+    position: usize,
+    // boundary crossings read minus reported, per boundary; negative if
+    // the DMA crossed one the reader has not
+    half: i8,
+    complete: i8,
+    overruns: u32,
+}
+
+// the pointer is only read
+unsafe impl Send for CircularReader {}
+
+impl CircularReader {
+    /// Reader of a buffer given over to the DMA; `None` if it is empty.
+    pub fn new(buffer: &'static mut [u8]) -> Option<Self> {
+        unsafe { CircularReader::from_raw(buffer.as_ptr(), buffer.len()) }
+    }
+    /// Reader of the `len` bytes at `buffer`; `None` if `len` is 0.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must stay valid for reads as long as the reader is used,
+    /// and no reference to them may exist while the DMA writes them.
+    pub unsafe fn from_raw(buffer: *const u8, len: usize) -> Option<Self> {
+        if len == 0 {
+            return None;
+        }
+        Some(CircularReader { buffer, len, position: 0, half: 0, complete: 0, overruns: 0 })
+    }
+    /// Size of the buffer.
+    pub fn capacity(&self) -> usize {
+        self.len
+    }
+    /// Position of the next unread byte.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+    /// Times the DMA was found to have lapped the reader.
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+    /// The channel raised its half-transfer event.
+    pub fn half_transfer(&mut self) {
+        self.half = self.half.saturating_sub(1);
+    }
+    /// The channel raised its transfer-complete event.
+    pub fn transfer_complete(&mut self) {
+        self.complete = self.complete.saturating_sub(1);
+    }
+    /// Copies bytes up to the DMA write position into `out`, returning how
+    /// many; repeat until it returns 0. `remaining` is the channel transfer
+    /// counter (NDTR), which counts down from `capacity()` and reloads in
+    /// circular mode.
+    ///
+    /// If the DMA has lapped the reader, the bytes written meanwhile are
+    /// dropped and `LineError::Overrun` returned; the stream goes on from
+    /// the write position.
+    pub fn read(&mut self, remaining: usize, out: &mut [u8]) -> Result<usize, LineError> {
+        let write = self.write_position(remaining);
+        let (half, complete) = self.crossings(write);
+        if self.half + (half as i8) < 0 || self.complete + (complete as i8) < 0 {
+            self.position = write;
+            self.half = 0;
+            self.complete = 0;
+            self.overruns = self.overruns.wrapping_add(1);
+            return Err(LineError::Overrun);
+        }
+        Ok(self.copy(write, out))
+    }
+    /// Like `read()`, up to a write position sampled earlier, e.g. when a
+    /// receive error occurred; the events are not checked.
+    pub fn read_to(&mut self, remaining: usize, out: &mut [u8]) -> usize {
+        let write = self.write_position(remaining);
+        self.copy(write, out)
+    }
+    /// Forwards the new bytes into a receive queue, followed by the overrun
+    /// if the DMA lapped the reader.
+    pub fn drain(&mut self, remaining: usize, producer: &mut Producer) -> usize {
+        let mut chunk = [0u8; 32];
+        let mut count = 0;
+        loop {
+            match self.read(remaining, &mut chunk) {
+                Ok(0) => return count,
+                Ok(n) => {
+                    for byte in &chunk[..n] {
+                        producer.push(*byte);
+                    }
+                    count += n;
+                },
+                Err(error) => {
+                    producer.push_error(error);
+                    return count;
+                },
+            }
+        }
+    }
+    fn write_position(&self, remaining: usize) -> usize {
+        (self.len - remaining.min(self.len)) % self.len
+    }
+    // whether the bytes up to `write` include the last one of each half
+    fn crossings(&self, write: usize) -> (bool, bool) {
+        let unread = (write + self.len - self.position) % self.len;
+        let includes = |at: usize| (at + self.len - self.position) % self.len < unread;
+        (includes(self.half_end()), includes(self.len - 1))
+    }
+    // last byte of the first half
+    fn half_end(&self) -> usize {
+        (self.len / 2).max(1) - 1
+    }
+    fn copy(&mut self, write: usize, out: &mut [u8]) -> usize {
+        let mut count = 0;
+        while self.position != write && count < out.len() {
+            // the DMA keeps writing the rest of the buffer behind our back
+            out[count] = unsafe { ptr::read_volatile(self.buffer.add(self.position)) };
+            count += 1;
+            if self.position == self.half_end() {
+                self.half = (self.half + 1).min(1);
+            }
+            if self.position == self.len - 1 {
+                self.complete = (self.complete + 1).min(1);
+            }
+            self.position = (self.position + 1) % self.len;
+        }
+        count
+    }
+}
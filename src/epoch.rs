@@ -0,0 +1,148 @@
+//! Grouping of received data into navigation epochs.
+//!
+//! The receiver outputs the sentences of one solution in a burst, all
+//! carrying the same UTC time. Updates go into a pending copy of
+//! `GPS_Data`; the snapshot returned to the application is replaced only
+//! once every expected sentence of the epoch has arrived, or when a new UTC
+//! time shows that the epoch is over.
+
+use crate::data::GPS_Data;
+use crate::nmea::GPS_Statement;
+use crate::tap::SentenceSet;
+use crate::types::GPSTime;
+
+/// Sentences output by the NEO-6 in its default configuration.
+pub const DEFAULT_SENTENCES: SentenceSet = SentenceSet::EMPTY
+    .with(GPS_Statement::RMC)
+    .with(GPS_Statement::VTG)
+    .with(GPS_Statement::GGA)
+    .with(GPS_Statement::GSA)
+    .with(GPS_Statement::GSV)
+    .with(GPS_Statement::GLL);
+
+pub struct EpochAssembler {
+    pending: GPS_Data,
+    snapshot: GPS_Data,
+    expected: SentenceSet,
+    seen: SentenceSet,
+    // pending changed since the last snapshot
+    updated: bool,
+    // the current epoch has been published
+    published: bool,
+    ready: bool,
+    // latest tick of the caller's clock
+    now: Option<u32>,
+    // a UTC time arrived since the last tick
+    unstamped: bool,
+}
+
+impl EpochAssembler {
+    pub fn new() -> Self {
+        EpochAssembler {
+            pending: GPS_Data::new(),
+            snapshot: GPS_Data::new(),
+            expected: DEFAULT_SENTENCES,
+            seen: SentenceSet::EMPTY,
+            updated: false,
+            published: false,
+            ready: false,
+            now: None,
+            unstamped: false,
+        }
+    }
+    /// Sentences that complete an epoch. With an empty set an epoch is only
+    /// published when the next one starts.
+    pub fn set_expected(&mut self, sentences: SentenceSet) {
+        self.expected = sentences;
+    }
+    pub fn expected(&self) -> SentenceSet {
+        self.expected
+    }
+    /// Seconds a field group may lag behind, see `GPS_Data::set_max_age`.
+    pub fn set_max_age(&mut self, seconds: u32) {
+        self.pending.set_max_age(seconds);
+        self.snapshot.set_max_age(seconds);
+    }
+    /// Ticks the data may go without a new UTC time, see
+    /// `GPS_Data::set_max_silence`.
+    pub fn set_max_silence(&mut self, ticks: u32) {
+        self.pending.set_max_silence(ticks);
+        self.snapshot.set_max_silence(ticks);
+    }
+    /// Samples the caller's clock: data received since the last call counts
+    /// as received at `now`.
+    pub fn set_now(&mut self, now: u32) {
+        self.now = Some(now);
+        if self.unstamped {
+            self.unstamped = false;
+            self.pending.set_received(now);
+            // published since the last tick, unless stamped before
+            if self.snapshot.get_epoch() == self.pending.get_epoch() || self.snapshot.get_received().is_none() {
+                self.snapshot.set_received(now);
+            }
+        }
+    }
+    /// Latest tick passed to `set_now()`.
+    pub fn now(&self) -> Option<u32> {
+        self.now
+    }
+    /// Called with the UTC time of an update before it is applied; a time
+    /// other than the pending one publishes the pending epoch.
+    pub fn begin(&mut self, time: GPSTime) {
+        if self.pending.get_epoch() != 0 && time != self.pending.get_time() {
+            self.publish();
+            self.seen = SentenceSet::EMPTY;
+            self.published = false;
+        }
+        self.unstamped = true;
+    }
+    /// Data of the epoch being assembled.
+    pub fn pending(&self) -> &GPS_Data {
+        &self.pending
+    }
+    pub fn pending_mut(&mut self) -> &mut GPS_Data {
+        self.updated = true;
+        &mut self.pending
+    }
+    /// Records a sentence of the pending epoch, publishing it once all
+    /// expected sentences have arrived.
+    pub fn received(&mut self, statement: GPS_Statement) {
+        self.seen = self.seen.with(statement);
+        if self.expected != SentenceSet::EMPTY && self.seen.contains_all(self.expected) {
+            self.publish();
+        }
+    }
+    /// Publishes the pending epoch now, unless it already was. Updates
+    /// arriving later in the same epoch go out with the next one.
+    pub fn publish(&mut self) {
+        if self.updated && !self.published {
+            self.snapshot = self.pending;
+            self.updated = false;
+            self.published = true;
+            self.ready = true;
+        }
+    }
+    /// Latest complete epoch.
+    pub fn snapshot(&self) -> &GPS_Data {
+        &self.snapshot
+    }
+    /// A snapshot was published since the last `take()`.
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+    /// Returns the snapshot if it is new.
+    pub fn take(&mut self) -> Option<GPS_Data> {
+        if self.ready {
+            self.ready = false;
+            Some(self.snapshot)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for EpochAssembler {
+    fn default() -> Self {
+        EpochAssembler::new()
+    }
+}
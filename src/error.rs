@@ -0,0 +1,124 @@
+use core::convert::Infallible;
+use core::fmt;
+
+/// Reasons a sentence can be rejected by the framer or the parsers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NmeaError {
+    /// No complete `$...` sentence, or no `,` after the sentence tag.
+    BadFrame,
+    /// Missing or wrong `*hh` checksum.
+    ChecksumMismatch,
+    /// Sentence tag this crate does not parse.
+    UnknownSentence,
+    /// Sentence has fewer fields than its type requires.
+    MissingField,
+    /// Non-digit character (or overflow) in a numeric field.
+    BadDigit,
+    /// Fixed-width field, such as `hhmmss` or `ddmmyy`, is too short.
+    FieldTooShort,
+    /// Well-formed field with an impossible value, e.g. 61 minutes of arc
+    /// or an `X` hemisphere.
+    InvalidValue,
+    /// Multi-part sentence (GSV) received out of order.
+    BadSequence,
+    /// Line longer than the tokenizer buffer.
+    LineTooLong,
+}
+
+impl fmt::Display for NmeaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let text = match self {
+            NmeaError::BadFrame => "malformed sentence",
+            NmeaError::ChecksumMismatch => "checksum mismatch",
+            NmeaError::UnknownSentence => "unknown sentence",
+            NmeaError::MissingField => "missing field",
+            NmeaError::BadDigit => "bad digit",
+            NmeaError::FieldTooShort => "field too short",
+            NmeaError::InvalidValue => "invalid value",
+            NmeaError::BadSequence => "out of sequence",
+            NmeaError::LineTooLong => "line too long",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Reasons a UBX frame can be rejected.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UbxError {
+    /// Wrong Fletcher checksum.
+    ChecksumMismatch,
+    /// Payload longer than the parser buffer or the 16-bit length field.
+    PayloadTooLong,
+    /// Output buffer too small for the encoded frame.
+    BufferTooSmall,
+    /// Payload length does not match the message type.
+    BadLength,
+    /// Class and id this crate does not decode.
+    UnknownMessage,
+    /// Frame arrived before the previous one was processed.
+    Overflow,
+    /// Sync chars not followed by the second one or by a plausible header,
+    /// most likely noise or binary data.
+    FalseSync,
+}
+
+impl fmt::Display for UbxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let text = match self {
+            UbxError::ChecksumMismatch => "checksum mismatch",
+            UbxError::PayloadTooLong => "payload too long",
+            UbxError::BufferTooSmall => "buffer too small",
+            UbxError::BadLength => "bad payload length",
+            UbxError::UnknownMessage => "unknown message",
+            UbxError::Overflow => "frame overflow",
+            UbxError::FalseSync => "false sync",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Receive errors flagged by the UART or the receive queue.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineError {
+    /// A byte arrived before the previous one was read and was lost.
+    Overrun,
+    /// No stop bit where expected, typically a baud rate mismatch.
+    Framing,
+    /// Noise detected on the line while sampling a byte.
+    Noise,
+    /// Parity bit wrong, only with parity enabled on the UART.
+    Parity,
+    /// Bytes dropped because the receive queue was full.
+    QueueOverflow,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let text = match self {
+            LineError::Overrun => "overrun",
+            LineError::Framing => "framing error",
+            LineError::Noise => "noise",
+            LineError::Parity => "parity error",
+            LineError::QueueOverflow => "receive queue overflow",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Error of a serial `Read` implementation the driver can classify.
+pub trait ReadError {
+    /// The UART condition behind the error, if it is one.
+    fn line_error(&self) -> Option<LineError>;
+}
+
+impl ReadError for LineError {
+    fn line_error(&self) -> Option<LineError> {
+        Some(*self)
+    }
+}
+
+impl ReadError for Infallible {
+    fn line_error(&self) -> Option<LineError> {
+        match *self {}
+    }
+}
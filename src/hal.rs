@@ -0,0 +1,45 @@
+//! Glue to the STM32F1 HAL, built with the `firmware` feature.
+
+use stm32f1xx_hal::{rtc, serial};
+
+use crate::error::{LineError, ReadError};
+use crate::rtc::RealTimeClock;
+
+impl ReadError for serial::Error {
+    fn line_error(&self) -> Option<LineError> {
+        match self {
+            serial::Error::Overrun => Some(LineError::Overrun),
+            serial::Error::Framing => Some(LineError::Framing),
+            serial::Error::Noise => Some(LineError::Noise),
+            serial::Error::Parity => Some(LineError::Parity),
+            _ => None,
+        }
+    }
+}
+
+/// Receive error flagged in a USART status register, most severe first;
+/// `None` if the flags are clear.
+pub fn sr_error(sr: &stm32f1xx_hal::pac::usart1::sr::R) -> Option<LineError> {
+    if sr.ore().bit_is_set() {
+        Some(LineError::Overrun)
+    } else if sr.fe().bit_is_set() {
+        Some(LineError::Framing)
+    } else if sr.ne().bit_is_set() {
+        Some(LineError::Noise)
+    } else if sr.pe().bit_is_set() {
+        Some(LineError::Parity)
+    } else {
+        None
+    }
+}
+
+/// The RTC counter in the backup domain, keeps counting on VBAT while the
+/// MCU is off.
+impl RealTimeClock for rtc::Rtc {
+    fn seconds(&self) -> u32 {
+        self.current_time()
+    }
+    fn set_seconds(&mut self, seconds: u32) {
+        self.set_time(seconds);
+    }
+}
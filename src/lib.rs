@@ -0,0 +1,52 @@
+//! Small, lightweight library for interfacing with u-blox NEO6MV2 GPS module.
+//!
+//! The NMEA sentence parsers, the UBX protocol codec and the data model do
+//! not depend on any HAL, so they build for the host as well as for the
+//! tracker firmware. The `NEO6` driver works on top of any `embedded_hal`
+//! serial port; the `hal` module, built with the `firmware` feature, adapts
+//! the STM32F1 HAL.
+#![no_std]
+#![allow(non_camel_case_types)]
+
+pub mod error;
+pub mod decimal;
+pub mod types;
+pub mod time;
+pub mod nmea;
+pub mod data;
+pub mod neo;
+pub mod stats;
+pub mod sky;
+pub mod ubx;
+pub mod cfg;
+pub mod queue;
+pub mod dma;
+pub mod tap;
+pub mod tokenizer;
+pub mod epoch;
+pub mod watchdog;
+pub mod rtc;
+pub mod pps;
+#[cfg(feature = "firmware")]
+pub mod hal;
+
+pub use error::{NmeaError, UbxError, LineError, ReadError};
+pub use decimal::Decimal;
+pub use types::{GPSTime, GPSDate, Position, Coordinate, GPSSatellite, FixType, FixMode, PositioningMode};
+pub use time::{UtcDateTime, Weekday};
+pub use nmea::{GPS_Statement, TalkerId, Sentence, RMC, GGA, GSA, GSV, VTG, GLL, ZDA};
+pub use nmea::{parse_rmc, parse_gsa, parse_gga, parse_gsv, parse_vtg, parse_gll, parse_zda, parse_coordinate, parse_sentence, split_sentence, verify_checksum};
+pub use data::{GPS_Data, Group, Stamp, Validity};
+pub use neo::{NEO6, New, NoTx};
+pub use stats::Statistics;
+pub use sky::{SkyView, GsvAssembler};
+pub use ubx::{UbxParser, UbxMessage, GpsFix, NavPosllh, NavStatus, NavSol, NavTimeUtc, NavPvt};
+pub use cfg::{CfgError, BaudError, DynamicModel, StartMode};
+pub use queue::{RingBuffer, Producer, Consumer};
+pub use dma::CircularReader;
+pub use tap::{TapMode, SentenceSet};
+pub use tokenizer::Tokenizer;
+pub use epoch::EpochAssembler;
+pub use watchdog::{Watchdog, Liveness, Recovery};
+pub use rtc::{RealTimeClock, RtcSync};
+pub use pps::{Pps, PpsTime};
@@ -3,41 +3,140 @@
 
 use panic_semihosting as _;
 
-use core::cell::{RefCell};
-use core::ops::{DerefMut};
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 
-use cortex_m_rt::entry;
+use cortex_m_rt::{entry, exception};
 use cortex_m::singleton;
+use cortex_m::peripheral::DWT;
 use cortex_m::interrupt::{
     free,
     Mutex
 };
 
-mod neo;
-use neo::{New, NEO6, GPS_Data};
+use neo6::{New, NEO6, CircularReader, LineError, Recovery, RtcSync};
+use neo6::hal::sr_error;
 use nb::block;
-use embedded_hal::serial::Write;
+use embedded_hal::serial::{Read, Write};
 
 use stm32f1xx_hal as hal;
 use hal::{
     prelude::*,
     stm32,
     delay::Delay,
-    serial::{self, Serial, Config, Rx1, Rx3, Tx1, Tx3},
-    stm32::{interrupt, NVIC, USART1, USART2, USART3},
+    rtc::Rtc,
+    dma::{self, dma1},
+    gpio::{Edge, ExtiPin},
+    timer::Timer,
+    serial::{Serial, Config, Rx1, Rx3, Tx1, Tx3},
+    stm32::{interrupt, NVIC, DMA1, EXTI, USART1, USART2, USART3},
 };
 
-static G_NEO: Mutex<RefCell<Option<NEO6<Rx, Tx>>>> = Mutex::new(RefCell::new(None));
+/// An idle line, DMA event or receive error since the main loop last read
+/// the DMA buffer.
+static G_RX_EVENT: AtomicBool = AtomicBool::new(false);
+/// DMA1 channel 3 events since the main loop last read the buffer.
+static G_DMA_HALF: AtomicBool = AtomicBool::new(false);
+static G_DMA_COMPLETE: AtomicBool = AtomicBool::new(false);
+/// USART3 receive error and the transfer counter when it was raised.
+static G_LINE_ERROR: Mutex<Cell<Option<(LineError, u16)>>> = Mutex::new(Cell::new(None));
+/// Cycle count of the last timepulse edge, until the main loop takes it.
+static G_PPS_EDGE: Mutex<Cell<Option<u32>>> = Mutex::new(Cell::new(None));
 
 pub type Rx = Rx3;
-pub type Tx = Tx1;
+pub type Tx = Tx3;
+pub type Log = Tx1;
 pub type USART = USART3;
 
+/// Rate the GPS UART is moved to at start-up.
+const GPS_BAUD_RATE: u32 = 115_200;
+const DMA_LEN: usize = 256;
+/// Silence before the GPS baud rate is probed again.
+const RECOVERY_MS: u32 = 10_000;
+/// SysTick wake-ups per second, for the watchdog while the receiver is
+/// silent.
+const TICK_HZ: u32 = 10;
+
+/// Circular DMA reception of USART3, read by the main loop. Receive errors
+/// are reported after the bytes received before them.
+struct DmaRx {
+    channel: dma1::C3,
+    reader: CircularReader,
+    error: Option<(LineError, u16)>,
+}
+
+impl DmaRx {
+    /// Copies the next received bytes into `out`, 0 if there are none.
+    fn read_chunk(&mut self, out: &mut [u8]) -> Result<usize, LineError> {
+        // the events go in before the transfer counter is sampled
+        if G_DMA_HALF.swap(false, Ordering::Relaxed) {
+            self.reader.half_transfer();
+        }
+        if G_DMA_COMPLETE.swap(false, Ordering::Relaxed) {
+            self.reader.transfer_complete();
+        }
+        if self.error.is_none() {
+            self.error = free(|cs| G_LINE_ERROR.borrow(cs).take());
+        }
+        if let Some((error, at)) = self.error {
+            let count = self.reader.read_to(at as usize, out);
+            if count > 0 {
+                return Ok(count);
+            }
+            self.error = None;
+            return Err(error);
+        }
+        self.reader.read(self.channel.get_ndtr() as usize, out)
+    }
+}
+
+/// Byte by byte, for the baud rate negotiation.
+impl Read<u8> for DmaRx {
+    type Error = LineError;
+    fn read(&mut self) -> nb::Result<u8, LineError> {
+        let mut byte = [0u8];
+        match self.read_chunk(&mut byte) {
+            Ok(0) => Err(nb::Error::WouldBlock),
+            Ok(_) => Ok(byte[0]),
+            Err(error) => Err(nb::Error::Other(error)),
+        }
+    }
+}
+
+/// Milliseconds since start-up, accumulated from the DWT cycle counter.
+/// `now()` has to be called at least once per wrap of the counter, about
+/// once a minute at 72 MHz.
+struct Millis {
+    last: u32,
+    cycles: u32,
+    ms: u32,
+    cycles_per_ms: u32,
+}
+
+impl Millis {
+    fn new(sysclk: u32) -> Self {
+        Millis {
+            last: DWT::get_cycle_count(),
+            cycles: 0,
+            ms: 0,
+            cycles_per_ms: sysclk / 1000,
+        }
+    }
+    fn now(&mut self) -> u32 {
+        let count = DWT::get_cycle_count();
+        self.cycles += count.wrapping_sub(self.last);
+        self.last = count;
+        self.ms = self.ms.wrapping_add(self.cycles / self.cycles_per_ms);
+        self.cycles %= self.cycles_per_ms;
+        self.ms
+    }
+}
+
 #[entry]
 fn main() -> ! {
     let dp = stm32::Peripherals::take().unwrap();
-    let cp = stm32::CorePeripherals::take().unwrap();
+    let mut cp = stm32::CorePeripherals::take().unwrap();
     
     let mut rcc = dp.RCC.constrain();
     let mut flash = dp.FLASH.constrain();
@@ -57,10 +156,25 @@ fn main() -> ! {
     let mut gpiob = dp.GPIOB.split(&mut rcc.apb2);
     let mut _gpioc = dp.GPIOC.split(&mut rcc.apb2);
 
-    // let mut dma_channels = dp.DMA1.split(&mut rcc.ahb);
+    let dma_channels = dp.DMA1.split(&mut rcc.ahb);
 
+    // wall-clock time from the LSE-driven RTC, set from GPS time
+    let mut pwr = dp.PWR;
+    let mut backup_domain = rcc.bkp.constrain(dp.BKP, &mut rcc.apb1, &mut pwr);
+    let mut rtc = Rtc::rtc(dp.RTC, &mut backup_domain);
+    let mut rtc_sync = RtcSync::new();
 
     let mut delay = Delay::new(cp.SYST, clocks);
+    // cycle counter for the GPS watchdog ticks
+    cp.DCB.enable_trace();
+    cp.DWT.enable_cycle_counter();
+
+    // the NEO-6 timepulse on PA0, timestamped with the cycle counter in the
+    // EXTI0 interrupt
+    let mut pps_pin = gpioa.pa0.into_floating_input(&mut gpioa.crl);
+    pps_pin.make_interrupt_source(&mut afio);
+    pps_pin.trigger_on_edge(&dp.EXTI, Edge::RISING);
+    pps_pin.enable_interrupt(&dp.EXTI);
 
     let tx_pin = gpioa.pa9.into_alternate_push_pull(&mut gpioa.crh);
     let rx_pin = gpioa.pa10.into_floating_input(&mut gpioa.crh);
@@ -98,67 +212,174 @@ fn main() -> ! {
         &mut rcc.apb1,
     );
 
-    gps_serial.listen(serial::Event::Rxne);
-
     let (gps_tx, gps_rx) = gps_serial.split();
-    let mut neo = NEO6::new(tx_buff, gps_rx, log_tx);
-    
-    let mut GPS_VALID=false;
 
-    free(|cs| {
-        G_NEO.borrow(cs).replace(Some(neo));
+    // Negotiate the GPS baud rate before the receive interrupt is unmasked;
+    // the MCU side follows by rewriting the USART3 baud rate register.
+    let probe_buff = singleton!(: [u8; LEN] = [0; LEN] ).unwrap();
+    let mut probe = NEO6::new(probe_buff, gps_rx, gps_tx);
+    let mut timer = Timer::tim2(dp.TIM2, &clocks, &mut rcc.apb1).start_count_down(1.hz());
+    let pclk1 = clocks.pclk1().0;
+    let set_baud = move |rate: u32| unsafe {
+        (*USART3::ptr()).brr.write(|w| w.bits(pclk1 / rate));
+    };
+    let negotiated = probe.negotiate_baud_rate(GPS_BAUD_RATE, set_baud, &mut timer, 1.hz());
+    if negotiated.is_err() {
+        for byte in b"GPS not detected\n" {
+            block!(log_tx.write(*byte)).ok();
+        }
+    }
+    // from here on the receiver is read by DMA
+    let (_gps_rx, gps_tx, _) = probe.release();
+
+    // DMA1 channel 3 copies USART3 data into a circular buffer. The idle
+    // line at the end of each burst and the half/complete transfer events
+    // wake the main loop, which parses the new data straight from there.
+    let dma_buff = singleton!(: [u8; DMA_LEN] = [0; DMA_LEN] ).unwrap();
+    let mut channel = dma_channels.3;
+    let usart3 = unsafe { &*USART3::ptr() };
+    channel.set_peripheral_address(&usart3.dr as *const _ as u32, false);
+    channel.set_memory_address(dma_buff.as_ptr() as u32, true);
+    channel.set_transfer_length(DMA_LEN);
+    channel.ch().cr.modify(|_, w| {
+        w.mem2mem().clear_bit()
+            .pl().medium()
+            .msize().bits8()
+            .psize().bits8()
+            .circ().set_bit()
+            .dir().clear_bit()
     });
+    channel.listen(dma::Event::HalfTransfer);
+    channel.listen(dma::Event::TransferComplete);
+    // with DMA reception framing, noise and overrun errors raise the error
+    // interrupt
+    usart3.cr3.modify(|_, w| w.dmar().set_bit().eie().set_bit());
+    usart3.cr1.modify(|_, w| w.idleie().set_bit());
+    channel.start();
+
+    let dma_rx = DmaRx {
+        channel,
+        reader: CircularReader::new(dma_buff).unwrap(),
+        error: None,
+    };
+    let mut neo = NEO6::new(tx_buff, dma_rx, gps_tx.unwrap()).with_log(log_tx);
 
     NVIC::unpend(stm32::Interrupt::USART3);
+    NVIC::unpend(stm32::Interrupt::DMA1_CHANNEL3);
+    NVIC::unpend(stm32::Interrupt::EXTI0);
     unsafe {
         NVIC::unmask(stm32::Interrupt::USART3);
+        NVIC::unmask(stm32::Interrupt::DMA1_CHANNEL3);
+        NVIC::unmask(stm32::Interrupt::EXTI0);
     };
     delay.delay_ms(500u16);
 
-
-    let mut gps_data = GPS_Data::new();
-    let mut update = true;
-
+    // wake the main loop regularly for the watchdog and the millisecond
+    // clock, even while the receiver is silent
+    let mut syst = delay.free();
+    syst.set_reload(clocks.sysclk().0 / TICK_HZ - 1);
+    syst.clear_current();
+    syst.enable_interrupt();
+    syst.enable_counter();
+
+    // a receiver that falls silent has most likely lost power and come back
+    // at its default baud rate
+    let mut millis = Millis::new(clocks.sysclk().0);
+    neo.watchdog_mut().set_recovery(Recovery::Reprobe, RECOVERY_MS);
+    neo.pps_mut().set_nominal(clocks.sysclk().0);
+
+    let mut chunk = [0u8; 64];
     loop {
-        if update {
-            free(|cs| {
-                let mut neo_ref = G_NEO.borrow(cs).borrow_mut();
-                if let Some(ref mut neo) = neo_ref.deref_mut() {
-                    neo.parse();
-                    if neo.data_valid() {
-                        neo.unlisten();
-                        GPS_VALID = true;
-                        update = false;
-                        gps_data = neo.get_data();
-                    } else if !neo.data_valid() {
-                        GPS_VALID = false;
-                    }
-                };
-            });
+        // the edge goes in before the sentences that label it
+        if let Some(count) = free(|cs| G_PPS_EDGE.borrow(cs).take()) {
+            neo.pps_capture(count);
         }
-        if GPS_VALID {
-            update = true;
-            free(|cs| {
-                let mut neo_ref = G_NEO.borrow(cs).borrow_mut();
-                if let Some(ref mut neo) = neo_ref.deref_mut() {
-                    neo.report();
-                    neo.listen();
-                }
-            });
-            GPS_VALID = false;
-
-
+        G_RX_EVENT.store(false, Ordering::Relaxed);
+        loop {
+            match neo.rx_mut().read_chunk(&mut chunk) {
+                Ok(0) => break,
+                Ok(count) => neo.feed(&chunk[..count]),
+                Err(error) => neo.line_error(error),
+            }
+        }
+        match neo.tick(millis.now()) {
+            Some(Recovery::Reprobe) => {
+                neo.negotiate_baud_rate(GPS_BAUD_RATE, set_baud, &mut timer, 1.hz()).ok();
+            },
+            Some(Recovery::Reset(start)) => {
+                neo.reset_receiver(start).ok();
+            },
+            None => (),
         }
+        // report once per navigation epoch
+        if let Some(data) = neo.take_epoch() {
+            rtc_sync.update(&mut rtc, &data);
+            if data.has_fix() {
+                neo.report();
+            }
+        }
+        neo.write_tap();
+        // sleep until the next interrupt; one raised since the flags were
+        // checked keeps WFI from sleeping, it runs once they are unmasked
+        free(|cs| {
+            if !G_RX_EVENT.load(Ordering::Relaxed) && G_PPS_EDGE.borrow(cs).get().is_none() {
+                cortex_m::asm::wfi();
+            }
+        });
     }
 }
 
+/// Idle line: the receiver finished a burst, the main loop reads it out.
+/// Also raised by receive errors, noted with the DMA position they occurred
+/// at so the main loop reports them after the bytes before them. Only the
+/// first of several errors the main loop has not taken yet is kept.
 #[interrupt]
 fn USART3() {
-    free(|cs| {
-        let mut neo_ref = G_NEO.borrow(cs).borrow_mut();
-        if let Some(ref mut neo) = neo_ref.deref_mut() {
-            neo.receive();
-        }
+    let usart3 = unsafe { &*USART3::ptr() };
+    let sr = usart3.sr.read();
+    let error = sr_error(&sr);
+    // reading SR then DR clears the idle and error flags; the byte in DR,
+    // if the DMA has not taken it yet, is the damaged one
+    let _ = usart3.dr.read();
+    let remaining = unsafe { (*DMA1::ptr()).ch3.ndtr.read().ndt().bits() };
+    if let Some(error) = error {
+        free(|cs| {
+            let cell = G_LINE_ERROR.borrow(cs);
+            if cell.get().is_none() {
+                cell.set(Some((error, remaining)));
+            }
+        });
+    }
+    if sr.idle().bit_is_set() || error.is_some() {
+        G_RX_EVENT.store(true, Ordering::Relaxed);
+    }
+}
 
-    });
+/// Half or all of the DMA buffer written.
+#[interrupt]
+fn DMA1_CHANNEL3() {
+    let dma1 = unsafe { &*DMA1::ptr() };
+    let isr = dma1.isr.read();
+    let (half, complete) = (isr.htif3().bit_is_set(), isr.tcif3().bit_is_set());
+    // only the flags seen, an event raised meanwhile stays pending
+    dma1.ifcr.write(|w| w.chtif3().bit(half).ctcif3().bit(complete));
+    if half {
+        G_DMA_HALF.store(true, Ordering::Relaxed);
+    }
+    if complete {
+        G_DMA_COMPLETE.store(true, Ordering::Relaxed);
+    }
+    G_RX_EVENT.store(true, Ordering::Relaxed);
 }
+
+/// Timepulse edge, at the start of a UTC second.
+#[interrupt]
+fn EXTI0() {
+    let count = DWT::get_cycle_count();
+    unsafe { (*EXTI::ptr()).pr.write(|w| w.pr0().set_bit()) };
+    free(|cs| G_PPS_EDGE.borrow(cs).set(Some(count)));
+}
+
+/// Periodic wake-up of the main loop.
+#[exception]
+fn SysTick() {}
@@ -1,665 +1,606 @@
-extern crate embedded_hal;
-use embedded_hal::serial::{Write};
+use embedded_hal::serial::{Read, Write};
+use embedded_hal::timer::CountDown;
 use nb::block;
 
-use crate::stm32::{USART1, USART2, USART3};
-use crate::serial::{Rx1, Tx1, Rx2, Tx2, Rx3, Tx3};
-use crate::serial::Event as SEvent;
-
 use core::fmt;
-
-pub fn atoi(barray: & [u8]) -> u32 {
-    let mut value = 0u32;
-    for element in barray.iter() {
-        value = value*10 + *element as u32 - b'0' as u32;
+use core::convert::Infallible;
+
+use crate::nmea::{GPS_Statement, Sentence, parse_sentence};
+use crate::tokenizer::Tokenizer;
+use crate::sky::GsvAssembler;
+use crate::ubx::{self, UbxParser, UbxMessage, parse_message};
+use crate::cfg::{self, CfgError, BaudError, DynamicModel, StartMode};
+use crate::tap::{Tap, TapMode, SentenceSet};
+use crate::error::{NmeaError, UbxError, LineError, ReadError};
+use crate::data::{GPS_Data, Validity};
+use crate::epoch::EpochAssembler;
+use crate::watchdog::{Watchdog, Liveness, Recovery};
+use crate::pps::{Pps, PpsTime};
+use crate::decimal::Decimal;
+use crate::stats::Statistics;
+
+/// Placeholder for a missing command channel or log sink.
+pub struct NoTx;
+
+impl Write<u8> for NoTx {
+    type Error = Infallible;
+    fn write(&mut self, _: u8) -> nb::Result<(), Infallible> {
+        Ok(())
     }
-    value
-}
-
-#[derive(Debug, Copy, Clone)]
-pub struct GPSFloat {
-    pub int: u32,
-    pub fract: u32,
-}
-
-impl fmt::Display for GPSFloat {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
-        write!(f, "{}.{}", self.int, self.fract)
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
     }
 }
 
-#[derive(Debug)]
-pub enum GPS_Statement {
-    GPRMC,
-    GPGSA,
-    GPGGA,
-    GPGSV,
-    Other,
-}
-
-#[derive(Debug)]
-pub enum Half {
-    First,
-    Second,
+/// Driver for the receiver on `rx`. Configuration commands go to the
+/// module over `tx`; the NMEA tap and `report()` go to the separate `log`
+/// sink. Both are optional.
+pub struct NEO6 <'a, Rx, Tx = NoTx, Log = NoTx> {
+    rx: Rx,
+    tx: Option<Tx>,
+    log: Option<Log>,
+    tokenizer: Tokenizer<'a>,
+    epoch: EpochAssembler,
+    stats: Statistics,
+    gsv: GsvAssembler,
+    ubx: UbxParser,
+    // class, id and ACK (true) or NAK (false) of the last acknowledge
+    ack: Option<(u8, u8, bool)>,
+    tap: Tap<'a>,
+    watchdog: Watchdog,
+    pps: Pps,
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct GPSTime {
-    pub hour: u8,
-    pub minute: u8,
-    pub second: u8,
+pub trait New<'a, Rx, Tx> {
+    fn new(buf: &'a mut [u8], rx: Rx, tx: Tx) -> Self;
 }
 
-impl GPSTime {
-    pub fn new() -> Self {
-        GPSTime {
-            hour: 0,
-            minute: 0,
-            second: 0,
-        }
+impl<'a, Rx, Tx> New<'a, Rx, Tx> for NEO6<'a, Rx, Tx> {
+    /// Driver with the module command channel `tx` and no log sink.
+    fn new(buf: &'a mut [u8], rx: Rx, tx: Tx) -> Self {
+        NEO6::build(buf, rx, Some(tx), None)
     }
 }
 
-impl fmt::Display for GPSTime {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
-        write!(f, "{}:{}:{}", self.hour, self.minute, self.second)
+impl<'a, Rx> NEO6<'a, Rx> {
+    /// Driver that only listens to the module.
+    pub fn receive_only(buf: &'a mut [u8], rx: Rx) -> Self {
+        NEO6::build(buf, rx, None, None)
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct GPSDate {
-    pub day: u8,
-    pub month: u8,
-    pub year: u8,
-}
-
-impl GPSDate {
-    pub fn new() -> Self {
-        GPSDate {
-            day: 0,
-            month: 0,
-            year: 0,
+impl<'a, Rx, Tx, Log> NEO6<'a, Rx, Tx, Log> {
+    fn build(buf: &'a mut [u8], rx: Rx, tx: Option<Tx>, log: Option<Log>) -> Self {
+        NEO6 {
+            rx,
+            tx,
+            log,
+            tokenizer: Tokenizer::new(buf),
+            epoch: EpochAssembler::new(),
+            stats: Statistics::new(),
+            gsv: GsvAssembler::new(),
+            ubx: UbxParser::new(),
+            ack: None,
+            tap: Tap::new(),
+            watchdog: Watchdog::new(),
+            pps: Pps::new(),
         }
     }
-}
-
-impl fmt::Display for GPSDate {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
-        write!(f, "{}/{}/{}", self.day, self.month, self.year)
+    /// Sends the NMEA tap and `report()` to `log`.
+    pub fn with_log<L>(self, log: L) -> NEO6<'a, Rx, Tx, L> {
+        NEO6 {
+            rx: self.rx,
+            tx: self.tx,
+            log: Some(log),
+            tokenizer: self.tokenizer,
+            epoch: self.epoch,
+            stats: self.stats,
+            gsv: self.gsv,
+            ubx: self.ubx,
+            ack: self.ack,
+            tap: self.tap,
+            watchdog: self.watchdog,
+            pps: self.pps,
+        }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct Position {
-    lattitude: GPSFloat,
-    ns_indicator: char,
-    longitude: GPSFloat,
-    ew_indicator: char,
-    altitude: GPSFloat,
-}
+/// Adapts a byte-oriented serial `Write` to `core::fmt::Write`.
+struct TextWriter<'w, Tx>(&'w mut Tx);
 
-impl Position {
-    pub fn new() -> Self {
-        Position {
-            lattitude: GPSFloat{int:0, fract:0},
-            ns_indicator: 'N',
-            longitude: GPSFloat{int:0, fract:0},
-            ew_indicator: 'E',
-            altitude: GPSFloat{int:0, fract:0},
+impl<'w, Tx: Write<u8>> fmt::Write for TextWriter<'w, Tx> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            block!(self.0.write(byte)).map_err(|_| fmt::Error)?;
         }
+        Ok(())
     }
 }
 
-
-impl fmt::Display for Position {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
-        write!(f, "Latt: {} {}; Long: {} {}; Alt: {} m", self.lattitude,  self.ns_indicator, self.longitude, self.ew_indicator, self.altitude)
+impl <'a, Rx, Tx, Log> NEO6 <'a, Rx, Tx, Log>
+where
+    Rx: Read<u8>,
+    Rx::Error: ReadError,
+    Tx: Write<u8>,
+    Log: Write<u8>,
+{
+    /// Serial receiver, e.g. to enable or disable its interrupts.
+    pub fn rx_mut(&mut self) -> &mut Rx {
+        &mut self.rx
     }
-}
-
-#[derive(Debug)]
-pub struct GPSSatellite {
-    ID: u8,
-    elevation: u8,
-    azimuth: u16,
-    SNR: u8,
-}
-
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum FixType {
-    NoFix,
-    GPSFix,
-    DifferentialFix,
-}
-
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum FixMode {
-    NoFix,
-    D2,
-    D3,
-}
-
-pub struct RMC {
-    valid: bool,
-    date: GPSDate,
-    speed: GPSFloat,
-    course: GPSFloat,
-}
-
-pub struct GGA {
-    position: Position,
-    satellites_used: u8,
-    fix: FixType,
-    time: GPSTime,
-}
-
-pub struct GSA {
-    hdop: GPSFloat,
-    vdop: GPSFloat,
-    pdop: GPSFloat,
-    fix: FixMode,
-    satellite_ids: [u8; 12],
-}
-
-
-pub struct MSG <'a> {
-    buffer: &'a mut [u8],
-    start: bool,
-    ptr: usize,
-    cap: usize,
-    len: usize,
-    last_read: Half,
-}
-
-impl <'a> MSG <'a> {
-    pub fn new(buff:&'a mut[u8], capactiy: usize) -> Self {
-        MSG {
-            buffer: buff,
-            start: false,
-            ptr: 0,
-            cap: capactiy,
-            len: 0,
-            last_read: Half::Second,
+    /// Command channel to the module.
+    pub fn tx_mut(&mut self) -> Option<&mut Tx> {
+        self.tx.as_mut()
+    }
+    /// Diagnostic log sink.
+    pub fn log_mut(&mut self) -> Option<&mut Log> {
+        self.log.as_mut()
+    }
+    pub fn release(self) -> (Rx, Option<Tx>, Option<Log>) {
+        (self.rx, self.tx, self.log)
+    }
+    /// Fresh fix of any quality in the latest epoch. Once `tick()` is
+    /// called, the fix also has to be recent as of the last tick.
+    pub fn data_valid(&self) -> bool {
+        match self.epoch.now() {
+            Some(now) => self.epoch.snapshot().has_fix_at(now),
+            None => self.epoch.snapshot().has_fix(),
         }
     }
-    pub fn add(&mut self, c: u8) {
-        match self.last_read {
-            Half::Second => {
-                if self.ptr < self.cap {
-                    if c == b'$' {
-                        self.start = true;
-                        self.buffer[self.ptr] = c;
-                        self.ptr += 1;
-                    } else if c == b'\n' {
-                        self.buffer[self.ptr] = c;
-                        self.ptr = self.cap;
-                        self.last_read = Half::First;
-                        self.len += 1;
-                        self.start=false;
-                    } else if self.start {
-                        self.buffer[self.ptr] = c;
-                        self.ptr += 1;
-                    }
+    /// See `GPS_Data::validity` and `GPS_Data::validity_at`, as for
+    /// `data_valid()`.
+    pub fn validity(&self, max_hdop: Decimal) -> Validity {
+        match self.epoch.now() {
+            Some(now) => self.epoch.snapshot().validity_at(now, max_hdop),
+            None => self.epoch.snapshot().validity(max_hdop),
+        }
+    }
+    /// Seconds the fix and position may lag behind the latest UTC time
+    /// before they are considered stale.
+    pub fn set_max_age(&mut self, seconds: u32) {
+        self.epoch.set_max_age(seconds);
+    }
+    /// Ticks, in the units of `tick()`, without a new UTC time before the
+    /// latest fix is considered stale.
+    pub fn set_max_silence(&mut self, ticks: u32) {
+        self.epoch.set_max_silence(ticks);
+    }
+    /// Sentences the receiver outputs every epoch; the epoch is published
+    /// as soon as all of them have arrived, otherwise when the next epoch
+    /// starts. Defaults to the NEO-6 factory output.
+    pub fn set_epoch_sentences(&mut self, sentences: SentenceSet) {
+        self.epoch.set_expected(sentences);
+    }
+    /// A new epoch was published since the last `take_epoch()`.
+    pub fn epoch_ready(&self) -> bool {
+        self.epoch.is_ready()
+    }
+    /// The latest epoch, if it is new.
+    pub fn take_epoch(&mut self) -> Option<GPS_Data> {
+        self.epoch.take()
+    }
+    /// Publishes the epoch being assembled without waiting for the rest of
+    /// it, e.g. when the receiver falls silent.
+    pub fn end_epoch(&mut self) {
+        self.epoch.publish();
+    }
+    /// Reads one byte from the receiver, meant to be called from the RX
+    /// interrupt, or from the main loop when `Rx` is a `queue::Consumer`.
+    /// Returns `false` if there was nothing to read.
+    ///
+    /// A receive error drops the sentence or UBX frame in progress, see
+    /// `line_error()`.
+    pub fn receive(&mut self) -> bool {
+        match self.rx.read() {
+            Ok(a) => {
+                self.feed(&[a]);
+                true
+            },
+            Err(nb::Error::Other(e)) => {
+                match e.line_error() {
+                    Some(error) => self.line_error(error),
+                    None => self.resync(),
                 }
+                true
             },
-            Half::First => {
-                if self.ptr < 2*self.cap {
-                    if c == b'$' {
-                        self.start = true;
-                        self.buffer[self.ptr] = c;
-                        self.ptr += 1;
-                    } else if c == b'\n' {
-                        self.buffer[self.ptr] = c;
-                        self.ptr = 0;
-                        self.last_read = Half::Second;
-                        self.len += 1;
-                        self.start = false;
-                    } else if self.start {
-                        self.buffer[self.ptr] = c;
-                        self.ptr += 1;
+            Err(nb::Error::WouldBlock) => false,
+        }
+    }
+    /// Counts a UART receive error and drops the sentence or UBX frame it
+    /// broke; reception resumes at the next `$` or UBX sync. For callers
+    /// passing data to `feed()` themselves.
+    pub fn line_error(&mut self, error: LineError) {
+        self.stats.count_line_error(error);
+        self.resync();
+    }
+    fn resync(&mut self) {
+        self.tokenizer.reset();
+        self.ubx.reset();
+    }
+    /// Receives and parses everything available from `rx`, then writes out
+    /// tap output.
+    pub fn poll(&mut self) {
+        while self.receive() {}
+        self.write_tap();
+    }
+    /// Processes received bytes, in chunks of any size. Sentences and UBX
+    /// frames are parsed as soon as they are complete; those that fail the
+    /// checksum or the parser are dropped and counted in `statistics()`.
+    ///
+    /// The stream may mix NMEA sentences and UBX frames: a UBX sync char
+    /// starts a binary frame, ending any sentence it interrupts.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.stats.bytes = self.stats.bytes.wrapping_add(data.len() as u32);
+        let mut rest = data;
+        while let Some(&first) = rest.first() {
+            if self.ubx.in_frame() || first == ubx::SYNC_1 {
+                if self.tokenizer.in_sentence() {
+                    // NMEA is plain ASCII, the sentence was cut short
+                    self.tokenizer.reset();
+                    self.stats.count_error(NmeaError::BadFrame);
+                }
+                let result = self.ubx.push(first);
+                if let Err(e) = result {
+                    self.stats.count_ubx_error(e);
+                    // not a frame after all, the byte is left for the NMEA parser
+                    if e == UbxError::FalseSync {
+                        continue;
                     }
                 }
-            },
+                rest = &rest[1..];
+                if result == Ok(true) {
+                    self.parse_ubx();
+                }
+                continue;
+            }
+            let end = rest.iter().skip(1).position(|c| *c == ubx::SYNC_1).map_or(rest.len(), |i| i + 1);
+            let mut nmea = &rest[..end];
+            rest = &rest[end..];
+            let NEO6 { tokenizer, tap, epoch, gsv, pps, stats, .. } = self;
+            while let Some(line) = tokenizer.next_line(&mut nmea) {
+                let result = line.and_then(|line| {
+                    tap.write_line(line);
+                    parse_line(epoch, gsv, pps, line)
+                });
+                match result {
+                    Ok(()) => stats.sentences += 1,
+                    Err(e) => stats.count_error(e),
+                }
+            }
         }
     }
-
-    pub fn clear(&mut self) {
-        self.len -= 1;
+    /// Writes out pending NMEA tap output without blocking. Call it
+    /// regularly while the tap is on, see `flush_tap()` to wait for it all.
+    pub fn write_tap(&mut self) {
+        if let Some(log) = self.log.as_mut() {
+            self.tap.flush(log);
+        }
     }
-    pub fn is_full(&self) -> bool {
-        if self.len == 2 { return true} else {return false};
+    fn parse_ubx(&mut self) {
+        if let Some((class, id, payload)) = self.ubx.frame() {
+            match parse_message(class, id, payload) {
+                Ok(message) => {
+                    self.stats.ubx_frames += 1;
+                    self.update_ubx(message);
+                },
+                Err(e) => self.stats.count_ubx_error(e),
+            }
+            self.ubx.clear();
+        }
     }
-    pub fn is_empty(&self) -> bool {
-        if self.len == 0 { return true} else {return false};
+    /// Copies received NMEA to the log sink, queued in `buffer` and written without
+    /// blocking from `write_tap()`.
+    pub fn set_tap(&mut self, mode: TapMode, buffer: &'a mut [u8]) {
+        self.tap.set(mode, buffer);
     }
-
-    pub fn get_line(&self) -> (GPS_Statement, &[u8]) {
-        if self.len > 0 {
-            let mut data = match self.last_read {
-                Half::First => &self.buffer[..self.cap],
-                Half::Second => &self.buffer[self.cap..],
-            };
-            let mut msg = data.splitn(2, |c| *c == 0x2C);       // 0d44 split at ,
-            let (cmd, info) = (msg.next().unwrap(), msg.next().unwrap());
-            let (cmd, info) = match cmd {
-                b"$GPRMC" => { (GPS_Statement::GPRMC, info) },
-                b"$GPGSA" => { (GPS_Statement::GPGSA, info) },
-                b"$GPGGA" => { (GPS_Statement::GPGGA, info) },
-            //     // b"$GPGSV" => {},
-                _ => { (GPS_Statement::Other, info) },
-
-            };
-            return (cmd, info);
+    pub fn set_tap_mode(&mut self, mode: TapMode) {
+        self.tap.set_mode(mode);
+    }
+    /// Writes out queued tap output, blocking until it is all sent.
+    pub fn flush_tap(&mut self) {
+        if let Some(log) = self.log.as_mut() {
+            while !self.tap.is_empty() {
+                self.tap.flush(log);
+            }
         }
-        (GPS_Statement::Other, &[0u8; 1])
     }
-}
-
-#[derive(Debug, Copy, Clone)]
-pub struct GPS_Data {
-
-    // from RMC
-    valid: bool,
-    date: GPSDate,
-    speed: GPSFloat,
-    course: GPSFloat,
-    // from GGA
-    position: Position,
-    satellites_used: u8,
-    fix: FixType,
-    time: GPSTime,
-    // from GSA
-    hdop: GPSFloat,
-    vdop: GPSFloat,
-    pdop: GPSFloat,
-    fix_mode: FixMode,
-    satellite_ids: [u8; 12],
-}
-
-impl GPS_Data {
-    pub fn new() -> Self {
-        GPS_Data { 
-            // from RMC
-            valid: false,
-            date: GPSDate::new(),
-            speed: GPSFloat{int: 0, fract:0},
-            course: GPSFloat{int:0, fract: 0},
-            // from GGA
-            position: Position::new(),
-            satellites_used: 0,
-            fix: FixType::NoFix,
-            time: GPSTime::new(),
-            // from GSA
-            hdop: GPSFloat{int:0, fract:0},
-            vdop: GPSFloat{int:0, fract:0},
-            pdop: GPSFloat{int:0, fract:0},
-            fix_mode: FixMode::NoFix,
-            satellite_ids: [0u8; 12],
+    fn update_ubx(&mut self, message: UbxMessage) {
+        if let Some(time) = message.time() {
+            self.epoch.begin(time);
+        }
+        match message {
+            UbxMessage::NavPosllh(data) => self.epoch.pending_mut().update_nav_posllh(data),
+            UbxMessage::NavStatus(data) => self.epoch.pending_mut().update_nav_status(data),
+            UbxMessage::NavSol(data) => self.epoch.pending_mut().update_nav_sol(data),
+            UbxMessage::NavTimeUtc(data) => self.epoch.pending_mut().update_nav_timeutc(data),
+            UbxMessage::NavPvt(data) => self.epoch.pending_mut().update_nav_pvt(data),
+            UbxMessage::AckAck(class, id) => self.ack = Some((class, id, true)),
+            UbxMessage::AckNak(class, id) => self.ack = Some((class, id, false)),
         }
     }
-    pub fn is_valid(&self) -> bool {
-        self.valid
+    /// Sends a UBX frame to the module.
+    pub fn send_ubx(&mut self, class: u8, id: u8, payload: &[u8]) -> Result<(), CfgError<Tx::Error>> {
+        let tx = self.tx.as_mut().ok_or(CfgError::NoChannel)?;
+        ubx::write_frame(tx, class, id, payload).map_err(CfgError::Serial)
     }
-    pub fn get_position(&self) -> Position {
-        self.position
+    /// Sends a CFG message and waits for its ACK-ACK or ACK-NAK, polling the
+    /// receiver meanwhile. Each of the `cfg::RETRIES` attempts waits up to
+    /// `timeout` of `timer`.
+    ///
+    /// Polls `rx` directly, so call it with the RX interrupt disabled.
+    pub fn configure<T>(&mut self, id: u8, payload: &[u8], timer: &mut T, timeout: T::Time) -> Result<(), CfgError<Tx::Error>>
+    where
+        T: CountDown,
+        T::Time: Copy,
+    {
+        for _ in 0..cfg::RETRIES {
+            self.ack = None;
+            self.send_ubx(ubx::class::CFG, id, payload)?;
+            timer.start(timeout);
+            loop {
+                self.receive();
+                self.write_tap();
+                match self.ack {
+                    Some((ubx::class::CFG, ack_id, true)) if ack_id == id => return Ok(()),
+                    Some((ubx::class::CFG, ack_id, false)) if ack_id == id => return Err(CfgError::Nak),
+                    _ => (),
+                }
+                if timer.wait().is_ok() {
+                    break;
+                }
+            }
+        }
+        Err(CfgError::Timeout)
     }
-    pub fn get_time(&self) -> GPSTime {
-        self.time
+    /// Navigation update period in ms, e.g. 200 for 5 Hz.
+    pub fn set_rate<T>(&mut self, period_ms: u16, timer: &mut T, timeout: T::Time) -> Result<(), CfgError<Tx::Error>>
+    where
+        T: CountDown,
+        T::Time: Copy,
+    {
+        self.configure(cfg::RATE, &cfg::rate(period_ms), timer, timeout)
     }
-    pub fn get_date(&self) -> GPSDate {
-        self.date
+    /// Moves the module UART to `baud_rate`. The receiver switches before
+    /// it acknowledges, so no ACK is awaited; reconfigure the MCU side next.
+    pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), CfgError<Tx::Error>> {
+        self.send_ubx(ubx::class::CFG, cfg::PRT, &cfg::prt_uart(baud_rate))?;
+        let tx = self.tx.as_mut().ok_or(CfgError::NoChannel)?;
+        block!(tx.flush()).map_err(CfgError::Serial)
     }
-    pub fn satellites_no(&self) -> u8 {
-        self.satellites_used
+    /// Polls the receiver until a sentence or UBX frame with a valid
+    /// checksum arrives, or `timeout` expires. Drops anything received
+    /// before.
+    pub fn wait_for_traffic<T>(&mut self, timer: &mut T, timeout: T::Time) -> bool
+    where
+        T: CountDown,
+    {
+        self.resync();
+        let before = self.stats.received();
+        timer.start(timeout);
+        loop {
+            self.receive();
+            self.write_tap();
+            if self.stats.received() != before {
+                return true;
+            }
+            if timer.wait().is_ok() {
+                return false;
+            }
+        }
     }
-    pub fn get_fix_type(&self) -> FixType {
-        self.fix
+    /// Finds the rate the module currently sends at, trying `cfg::BAUD_RATES`
+    /// in turn. `set_baud` reconfigures the MCU serial port; it is left at
+    /// the detected rate.
+    pub fn probe_baud_rate<T, F>(&mut self, set_baud: &mut F, timer: &mut T, timeout: T::Time) -> Option<u32>
+    where
+        T: CountDown,
+        T::Time: Copy,
+        F: FnMut(u32),
+    {
+        for &rate in cfg::BAUD_RATES.iter() {
+            set_baud(rate);
+            if self.wait_for_traffic(timer, timeout) {
+                return Some(rate);
+            }
+        }
+        None
     }
-    pub fn get_fix_mode(&self) -> FixMode {
-        self.fix_mode
+    /// Moves the module and the MCU serial port to `target` and returns the
+    /// rate in use afterwards. When nothing valid arrives at `target` the
+    /// module is looked for at the previous rate and at `target` again. One
+    /// found at the previous rate is sent back to `cfg::DEFAULT_BAUD_RATE`;
+    /// if it ignores that too, the MCU stays at the rate it was found at.
+    pub fn negotiate_baud_rate<T, F>(&mut self, target: u32, mut set_baud: F, timer: &mut T, timeout: T::Time) -> Result<u32, BaudError<Tx::Error>>
+    where
+        T: CountDown,
+        T::Time: Copy,
+        F: FnMut(u32),
+    {
+        let current = match self.probe_baud_rate(&mut set_baud, timer, timeout) {
+            Some(rate) => rate,
+            None => {
+                set_baud(cfg::DEFAULT_BAUD_RATE);
+                return Err(BaudError::NotDetected);
+            },
+        };
+        if current == target {
+            return Ok(target);
+        }
+        self.set_baud_rate(target).map_err(BaudError::Command)?;
+        set_baud(target);
+        if self.wait_for_traffic(timer, timeout) {
+            return Ok(target);
+        }
+        // the module may have ignored the switch, or be slow to come up
+        let mut found = None;
+        for &rate in [current, target].iter() {
+            set_baud(rate);
+            if self.wait_for_traffic(timer, timeout) {
+                found = Some(rate);
+                break;
+            }
+        }
+        let rate = match found {
+            // it did switch after all
+            Some(rate) if rate == target => return Ok(target),
+            Some(rate) => rate,
+            None => {
+                set_baud(cfg::DEFAULT_BAUD_RATE);
+                return Err(BaudError::NotDetected);
+            },
+        };
+        if rate == cfg::DEFAULT_BAUD_RATE {
+            return Ok(rate);
+        }
+        // the fallback command goes out at the rate the module listens on
+        self.set_baud_rate(cfg::DEFAULT_BAUD_RATE).map_err(BaudError::Command)?;
+        set_baud(cfg::DEFAULT_BAUD_RATE);
+        if self.wait_for_traffic(timer, timeout) {
+            return Ok(cfg::DEFAULT_BAUD_RATE);
+        }
+        set_baud(rate);
+        if self.wait_for_traffic(timer, timeout) {
+            Ok(rate)
+        } else {
+            Err(BaudError::NotDetected)
+        }
     }
-    pub fn get_speed(&self) -> GPSFloat {
-        self.speed
+    /// Output rate of an NMEA sentence in navigation epochs, 0 disables it.
+    pub fn set_sentence_rate<T>(&mut self, sentence: GPS_Statement, rate: u8, timer: &mut T, timeout: T::Time) -> Result<(), CfgError<Tx::Error>>
+    where
+        T: CountDown,
+        T::Time: Copy,
+    {
+        self.configure(cfg::MSG, &cfg::msg_rate(cfg::NMEA_CLASS, sentence.ubx_id(), rate), timer, timeout)
     }
-    pub fn get_course(&self) -> GPSFloat {
-        self.course
+    /// Output rate of a UBX message in navigation epochs, 0 disables it.
+    pub fn set_ubx_rate<T>(&mut self, class: u8, id: u8, rate: u8, timer: &mut T, timeout: T::Time) -> Result<(), CfgError<Tx::Error>>
+    where
+        T: CountDown,
+        T::Time: Copy,
+    {
+        self.configure(cfg::MSG, &cfg::msg_rate(class, id, rate), timer, timeout)
     }
-    pub fn update_rmc (&mut self, data: RMC) {
-        self.valid = data.valid;
-        self.date = data.date;
-        self.speed = data.speed;
-        self.course = data.course;
+    pub fn set_dynamic_model<T>(&mut self, model: DynamicModel, timer: &mut T, timeout: T::Time) -> Result<(), CfgError<Tx::Error>>
+    where
+        T: CountDown,
+        T::Time: Copy,
+    {
+        self.configure(cfg::NAV5, &cfg::nav5(model), timer, timeout)
     }
-    pub fn update_gga (&mut self, data: GGA) {
-        self.position = data.position;
-        self.satellites_used = data.satellites_used;
-        self.fix = data.fix;
-        self.time = data.time;
+    /// Saves the current configuration to `devices`, see `cfg::device`.
+    pub fn save_config<T>(&mut self, devices: u8, timer: &mut T, timeout: T::Time) -> Result<(), CfgError<Tx::Error>>
+    where
+        T: CountDown,
+        T::Time: Copy,
+    {
+        self.configure(cfg::CFG, &cfg::save(devices), timer, timeout)
     }
-    pub fn update_gsa (&mut self, data: GSA) {
-        self.hdop = data.hdop;
-        self.vdop = data.vdop;
-        self.pdop = data.pdop;
-        self.fix_mode = data.fix;
-        self.satellite_ids = data.satellite_ids;
+    /// Restarts the receiver with CFG-RST. It comes back with its saved
+    /// configuration, possibly at another baud rate.
+    pub fn reset_receiver(&mut self, start: StartMode) -> Result<(), CfgError<Tx::Error>> {
+        self.send_ubx(ubx::class::CFG, cfg::RST, &cfg::rst(start))?;
+        let tx = self.tx.as_mut().ok_or(CfgError::NoChannel)?;
+        block!(tx.flush()).map_err(CfgError::Serial)
     }
-}
-
-pub struct NEO6 <'a, Rx, Tx> {
-    rx: Rx,
-    tx: Tx,
-    buffer: MSG<'a>,
-    gps_data: GPS_Data,
-}
-
-pub trait New<'a, Rx, Tx> {
-    fn new(buf: &'a mut [u8], rx: Rx, tx: Tx) -> NEO6<Rx, Tx>;
-}
-
-impl<'a, Rx, Tx> New<'a, Rx, Tx> for NEO6<'a, Rx, Tx> {
-    fn new(buf: &'a mut [u8], rx: Rx, tx: Tx) -> Self {
-        let buf_len =buf.len();
-        NEO6 {
-            rx: rx,
-            tx: tx,
-            buffer: MSG::new(buf, buf_len/2),
-            gps_data: GPS_Data::new(),
+    /// Updates the liveness monitor with the receiver activity up to `now`,
+    /// a monotonic tick count. Call it regularly from the main loop; returns
+    /// the recovery action set with `watchdog_mut()` once it is due.
+    pub fn tick(&mut self, now: u32) -> Option<Recovery> {
+        self.epoch.set_now(now);
+        let fix = self.data_valid();
+        self.watchdog.update(now, self.stats.bytes, self.stats.received(), fix)
+    }
+    /// Receiver state as of the last `tick()`.
+    pub fn liveness(&self) -> Liveness {
+        self.watchdog.state()
+    }
+    pub fn watchdog(&self) -> &Watchdog {
+        &self.watchdog
+    }
+    pub fn watchdog_mut(&mut self) -> &mut Watchdog {
+        &mut self.watchdog
+    }
+    /// Timestamp of a timepulse edge. Pass it before the bytes received
+    /// after the edge are parsed, so the next RMC or ZDA labels it.
+    pub fn pps_capture(&mut self, count: u32) {
+        self.pps.capture(count);
+    }
+    /// UTC time at the timestamp `count`, from the last labelled edge.
+    pub fn pps_now(&self, count: u32) -> Option<PpsTime> {
+        self.pps.now(count)
+    }
+    pub fn pps(&self) -> &Pps {
+        &self.pps
+    }
+    pub fn pps_mut(&mut self) -> &mut Pps {
+        &mut self.pps
+    }
+    /// Latest complete epoch.
+    pub fn get_data(&self) -> GPS_Data {
+        *self.epoch.snapshot()
+    }
+    /// Data of the epoch still being received.
+    pub fn get_pending(&self) -> GPS_Data {
+        *self.epoch.pending()
+    }
+    pub fn statistics(&self) -> Statistics {
+        Statistics {
+            tap_dropped: self.tap.dropped(),
+            ..self.stats
         }
     }
+    /// Writes the latest time, date, position, speed and course to the log sink.
+    pub fn report(&mut self) {
+        use core::fmt::Write;
+        let log = match self.log.as_mut() {
+            Some(log) => log,
+            None => return,
+        };
+        let data = self.epoch.snapshot();
+        let mut out = TextWriter(log);
+        writeln!(out, "{}", data.get_time()).ok();
+        writeln!(out, "{}", data.get_date()).ok();
+        writeln!(out, "{}", data.get_position()).ok();
+        writeln!(out, "Speed: {} knots", data.get_speed()).ok();
+        writeln!(out, "Course: {} degrees", data.get_course()).ok();
+    }
 }
 
-macro_rules! neo {
-    ($(
-        $(#[$meta:meta])*
-        NEO6: (
-            $USARTX:ident,
-            $rxX: ident,
-            $txX: ident,
-        ),
-    )+) => {
-        $(
-            $(#[$meta])*
-            impl <'a> NEO6 <'a, $rxX, $txX> {
-                pub fn listen(&mut self) {
-                    self.rx.listen(crate::serial::Event::Rxne);
-                }
-                pub fn unlisten(&mut self) {
-                    self.rx.unlisten(crate::serial::Event::Rxne);
-                }
-                pub fn data_valid(&self) -> bool {
-                    self.gps_data.is_valid() && self.gps_data.get_fix_type() != FixType::NoFix && self.gps_data.get_fix_mode() != FixMode::NoFix
-                }
-                pub fn receive(&mut self) {
-                    let mut i = 0;
-                    if let Some(ev) = self.rx.which_event() {
-                        match ev {
-                            SEvent::Rxne => {
-                                self.rx.clear_event();
-                                if !self.buffer.is_full() {
-                                    let a = unsafe { (*$USARTX::ptr()).dr.read().bits() as u8};            
-                                    self.buffer.add(a);
-                                    block!(self.tx.write(a)).ok();
-                                    i += 1;
-                                }
-                            },
-                            _ => {
-                                self.rx.clear_event();
-                            }
-                        }
-                    }
-                }
-                pub fn get_line(&self) -> (GPS_Statement, &[u8]) {
-                    self.buffer.get_line()
-                }
-                pub fn buffer_is_empty(&self) -> bool {
-                    self.buffer.is_empty()
-                }
-                pub fn clear_buffer(&mut self) {
-                    self.buffer.clear();
-                }
-                pub fn parse(&mut self) {
-                    if !self.buffer.is_empty() {
-                        let (mut cmd, mut info) = self.get_line();
-                        match cmd {
-                                GPS_Statement::GPRMC => { 
-                                    let rmc_data = self.parse_rmc(info);
-                                    self.gps_data.update_rmc(rmc_data);
-                                },
-                                GPS_Statement::GPGSA => {
-                                    let gsa_data = self.parse_gsa(info);
-                                    self.gps_data.update_gsa(gsa_data);
-                                },
-                                GPS_Statement::GPGGA => {
-                                    let gga_data = self.parse_gga(info);
-                                    self.gps_data.update_gga(gga_data);
-                                },
-                                _ => (),
-            
-                        }
-                    
-                        self.buffer.clear();
-                    }
-                }
-                pub fn parse_rmc(&self, data: &[u8]) -> RMC {
-                    let mut gpsdate = GPSDate::new();
-                    let mut validity = false;
-                    let mut speed = GPSFloat{int:0, fract:0};
-                    let mut course = GPSFloat{int:0, fract:0};
-            
-                    for (i, field) in data.split(|c| *c == b',').enumerate() {
-                        if field.len() > 0 {
-                            match i {
-                                // GPSTIME
-                                0 => {
-                                    // let (hour, minute, seconds) = (atoi(&field[..2]) as u8, atoi(&field[2..4]) as u8, atoi(& field[4..6]) as u8);
-                                },
-                                // Receiver Validity
-                                1 => {
-                                    validity = if field[0] == b'A' {
-                                        true
-                                    } else {
-                                        false
-                                    };
-                                },
-                                // Lattitude
-                                2 => {
-                                },
-                                // HEMISPHERE indicator
-                                3 => {
-                                },
-                                // LONGITUDE
-                                4 => {
-                                },
-                                // H ind
-                                5 => {
-                                },
-                                // Speed over ground
-                                6 => {
-                                    let mut speed_iter = field.split(|c| *c == b'.');
-                                    let (int, fract) = (atoi(speed_iter.next().unwrap()), atoi(speed_iter.next().unwrap()));
-                                    speed = GPSFloat{int:int, fract: fract};
-                                },
-                                // Course over ground
-                                7 => {
-                                    let mut course_iter = field.split(|c| *c == b'.');
-                                    let (int, fract) = (atoi(course_iter.next().unwrap()), atoi(course_iter.next().unwrap()));
-                                    course = GPSFloat{int:int, fract: fract};            
-                                },
-                                // GPSDATE
-                                8 => {
-                                    let (day, month, year) = (atoi(&field[..2]) as u8, atoi(&field[2..4]) as u8, atoi(&field[4..6]) as u8);
-                                    gpsdate = GPSDate{day, month, year};
-                                },
-                                _ => (),
-                            }
-                        }
-                    } 
-                    RMC {
-                        valid: validity, 
-                        date: gpsdate,
-                        speed: speed,
-                        course: course,
-                    }
-                }
-                pub fn parse_gsa(&self, data: &[u8]) -> GSA {
-                    let mut fixmode = FixMode::NoFix;
-                    let mut sat_ids = [0u8; 12];
-                    let mut hdop= GPSFloat{int:0, fract:0};
-                    let mut vdop= GPSFloat{int:0, fract:0};
-                    let mut pdop= GPSFloat{int:0, fract:0};
-            
-                    for (i, field) in data.split(|c| *c == b',').enumerate() {
-                        if field.len() > 0 {
-                            match i {
-                                // Mode 1
-                                0 => {
-            
-                                },
-                                // Mode 2 - FixMode
-                                1 => {
-                                    fixmode = match atoi(&field) {
-                                        2 => FixMode::D2,
-                                        3 => FixMode::D3,
-                                        _ => FixMode::NoFix,
-                                    };
-                                },
-                                // IDs of sattelites in use
-                                2..=12 => {
-                                    let id = atoi(&field) as u8;
-                                    sat_ids[i-2] = id;
-                                },
-                                // Position Dilution of Precision
-                                13 => {
-                                    let (int, fract) = (field[0] as u32, field[2] as u32);
-                                    pdop = GPSFloat{int:int, fract:fract};
-                                },
-                                // Horizontal Dilution of Precision
-                                14 => {
-                                    let (int, fract) = (field[0] as u32, field[2] as u32);
-                                    hdop = GPSFloat{int:int, fract:fract};
-                                },
-                                // Vertical Dilution of Precision
-                                15 => {
-                                    let (int, fract) = (field[0] as u32, field[2] as u32);
-                                    vdop = GPSFloat{int:int, fract:fract};
-                                },
-                                _ => (),
-                            }
-                        }
-                    }
-                    GSA {
-                        hdop: hdop,
-                        vdop: vdop,
-                        pdop: pdop,
-                        fix: fixmode,
-                        satellite_ids: sat_ids,
-                    }
-                }
-                pub fn parse_gga(&self, data: &[u8]) -> GGA {
-                    let mut gpstime = GPSTime::new();
-                    let mut pos = Position::new();
-                    let mut satellites = 0u8;
-                    let mut fix_mode = FixType::NoFix;
-            
-                    for (i, field) in data.split(|c| *c == b',').enumerate() {
-                        if field.len() > 0 {
-                            match i {
-                                // UTC time
-                                0 => {
-                                    let (hour, minute, seconds) = (atoi(&field[..2]) as u8, atoi(&field[2..4]) as u8, atoi(& field[4..6]) as u8);
-                                    gpstime.hour = hour;
-                                    gpstime.minute = minute;
-                                    gpstime.second = seconds;
-                                },
-                                // Lattitude
-                                1 => {
-                                    let (lat_int, lat_fract) = (atoi(&field[..2]), atoi(&field[2..4]));
-                                    pos.lattitude = GPSFloat{int:lat_int, fract:lat_fract};        
-                                },
-                                // N/S indicator
-                                2 => {
-                                    pos.ns_indicator = field[0] as char;
-                                },
-                                // Longitude
-                                3 => {
-                                    let (long_int, long_fract) = (atoi(&field[..3]), atoi(&field[3..5]));
-                                    pos.longitude = GPSFloat{int: long_int, fract:long_fract};
-                                },
-                                // E/W indicator
-                                4 => {
-                                    pos.ew_indicator = field[0] as char;
-                                },
-                                // FIX
-                                5 => {
-                                    fix_mode = match atoi(&field) {
-                                        0 => FixType::NoFix,
-                                        1 => FixType::GPSFix,
-                                        2 => FixType::DifferentialFix,
-                                        _ => FixType::NoFix,
-                                    };
-                                },
-                                // Satellites used
-                                6 => {
-                                    satellites = atoi(&field) as u8;
-                                },
-                                // Altitude 
-                                8 => {
-                                    let mut alt_iter = field.split(|c| *c == b'.');
-                                    let (alt_int, alt_frac) = (atoi(alt_iter.next().unwrap()), atoi(alt_iter.next().unwrap()));
-                                    pos.altitude = GPSFloat{int:alt_int, fract:alt_frac as u32};
-                                },
-                                _ => (),
-                            }
-                        }
-                    }
-
-                    GGA {
-                        time: gpstime, 
-                        position: pos,
-                        satellites_used: satellites,
-                        fix: fix_mode,
-                    }
-            
-                }
-                pub fn get_data(&self) -> GPS_Data {
-                    self.gps_data
-                }
-                pub fn report(&mut self) {
-                    use core::fmt::Write;
-                    write!(self.tx, "{}\n", self.gps_data.get_time());
-                    write!(self.tx, "{}\n", self.gps_data.get_date());
-                    write!(self.tx, "{}\n", self.gps_data.get_position());
-                    write!(self.tx, "Speed: {} knots\n", self.gps_data.get_speed());
-                    write!(self.tx, "Course: {} degrees\n", self.gps_data.get_course());
-
-                }
+fn parse_line(epoch: &mut EpochAssembler, gsv: &mut GsvAssembler, pps: &mut Pps, line: &[u8]) -> Result<(), NmeaError> {
+    let (talker, sentence) = parse_sentence(line)?;
+    if let Some(time) = sentence.time() {
+        epoch.begin(time);
+    }
+    let gps_data = epoch.pending_mut();
+    match sentence {
+        Sentence::RMC(rmc_data) => gps_data.update_rmc(rmc_data),
+        Sentence::GSA(gsa_data) => gps_data.update_gsa(gsa_data),
+        Sentence::GGA(gga_data) => gps_data.update_gga(gga_data),
+        Sentence::GSV(gsv_data) => {
+            match gsv.add(talker, &gsv_data)? {
+                Some(view) => gps_data.update_sky_view(view),
+                // the epoch is not complete before the last part
+                None => return Ok(()),
             }
-        )+
-        
+        },
+        Sentence::VTG(vtg_data) => gps_data.update_vtg(vtg_data),
+        Sentence::GLL(gll_data) => gps_data.update_gll(gll_data),
+        Sentence::ZDA(zda_data) => gps_data.update_zda(zda_data),
     }
+    match sentence {
+        Sentence::RMC(_) | Sentence::GGA(_) | Sentence::GLL(_) => gps_data.set_talker(talker),
+        _ => (),
+    }
+    // the timepulse is only trusted to be on UTC while the data is valid
+    match sentence {
+        Sentence::RMC(_) | Sentence::ZDA(_) if gps_data.is_valid() => {
+            if let Some(utc) = gps_data.get_utc() {
+                pps.label(utc);
+            }
+        },
+        _ => (),
+    }
+    epoch.received(sentence.statement());
+    Ok(())
 }
-
-neo! {
-    NEO6: (
-        USART1,
-        Rx1,
-        Tx1,
-    ),
-    NEO6: (
-        USART2,
-        Rx2,
-        Tx2,
-    ),
-    NEO6: (
-        USART3,     
-        Rx3,
-        Tx3,
-
-    ),
-    NEO6: (
-        USART3,     
-        Rx3,
-        Tx1,
-
-    ),
-}
\ No newline at end of file
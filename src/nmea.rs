@@ -0,0 +1,674 @@
+use core::convert::TryFrom;
+
+use crate::decimal::Decimal;
+use crate::types::{GPSTime, GPSDate, Position, Coordinate, GPSSatellite, FixType, FixMode, PositioningMode};
+use crate::error::NmeaError;
+
+pub fn atoi(barray: & [u8]) -> Result<u32, NmeaError> {
+    let mut value = 0u32;
+    for element in barray.iter() {
+        if !element.is_ascii_digit() {
+            return Err(NmeaError::BadDigit);
+        }
+        value = value.checked_mul(10)
+            .and_then(|v| v.checked_add((*element - b'0') as u32))
+            .ok_or(NmeaError::BadDigit)?;
+    }
+    Ok(value)
+}
+
+/// Fails with `MissingField` when `data` has fewer than `count` fields.
+fn require_fields(data: &[u8], count: usize) -> Result<(), NmeaError> {
+    if data.split(|c| *c == b',').count() < count {
+        return Err(NmeaError::MissingField);
+    }
+    Ok(())
+}
+
+fn require_len(field: &[u8], len: usize) -> Result<(), NmeaError> {
+    if field.len() < len {
+        return Err(NmeaError::FieldTooShort);
+    }
+    Ok(())
+}
+
+/// Decodes a `ddmm.mmmmm` (`max_degrees` 90) or `dddmm.mmmmm` (180) field
+/// and its `N`/`S`/`E`/`W` hemisphere field into a signed `Coordinate`.
+/// Minute decimals beyond the fifth are truncated.
+pub fn parse_coordinate(field: &[u8], hemisphere: &[u8], max_degrees: u32) -> Result<Coordinate, NmeaError> {
+    let dot = field.iter().position(|c| *c == b'.').unwrap_or(field.len());
+    if dot < 3 {
+        return Err(NmeaError::FieldTooShort);
+    }
+    let degrees = atoi(&field[..dot - 2])?;
+    let minutes = atoi(&field[dot - 2..dot])?;
+    let decimals = field.get(dot + 1..).unwrap_or(&[]);
+    if !decimals.iter().all(|c| c.is_ascii_digit()) {
+        return Err(NmeaError::BadDigit);
+    }
+    let fract = decimals.iter().chain(core::iter::repeat(&b'0')).take(5)
+        .fold(0, |value, c| value * 10 + (c - b'0') as u32);
+    if minutes >= 60 || degrees > max_degrees || degrees * 60 + minutes > max_degrees * 60 {
+        return Err(NmeaError::InvalidValue);
+    }
+    let value = ((degrees * 60 + minutes) * 100_000 + fract) as i32;
+    match hemisphere {
+        b"N" | b"E" => Ok(Coordinate::from_minutes_e5(value)),
+        b"S" | b"W" => Ok(Coordinate::from_minutes_e5(-value)),
+        b"" => Err(NmeaError::MissingField),
+        _ => Err(NmeaError::InvalidValue),
+    }
+}
+
+/// `hhmmss[.ss]` UTC time field; the fraction is kept to the millisecond.
+pub fn parse_time(field: &[u8]) -> Result<GPSTime, NmeaError> {
+    require_len(field, 6)?;
+    let (hour, minute, second) = (atoi(&field[..2])?, atoi(&field[2..4])?, atoi(&field[4..6])?);
+    // 60 is a leap second
+    if hour > 23 || minute > 59 || second > 60 {
+        return Err(NmeaError::InvalidValue);
+    }
+    // optional `.ss` fraction, to the millisecond
+    let fraction = match field[6..].split_first() {
+        Some((b'.', digits)) => digits,
+        Some(_) => return Err(NmeaError::BadDigit),
+        None => &[],
+    };
+    if !fraction.iter().all(|c| c.is_ascii_digit()) {
+        return Err(NmeaError::BadDigit);
+    }
+    let millisecond = fraction.iter().chain(core::iter::repeat(&b'0')).take(3)
+        .fold(0, |value, c| value * 10 + (c - b'0') as u16);
+    Ok(GPSTime{hour: hour as u8, minute: minute as u8, second: second as u8, millisecond})
+}
+
+/// Single-letter field, `A`, `V`, `N`, ...
+fn parse_char(field: &[u8]) -> Result<u8, NmeaError> {
+    match field {
+        [c] => Ok(*c),
+        _ => Err(NmeaError::InvalidValue),
+    }
+}
+
+fn parse_mode(field: &[u8]) -> Result<PositioningMode, NmeaError> {
+    PositioningMode::from_byte(parse_char(field)?).ok_or(NmeaError::InvalidValue)
+}
+
+/// Parses an optional coordinate pair, empty while the receiver has no fix.
+fn parse_lat_lon(lat: &[u8], ns: &[u8], lon: &[u8], ew: &[u8]) -> Result<(Coordinate, Coordinate), NmeaError> {
+    if lat.is_empty() && lon.is_empty() {
+        return Ok((Coordinate::default(), Coordinate::default()));
+    }
+    Ok((parse_coordinate(lat, ns, 90)?, parse_coordinate(lon, ew, 180)?))
+}
+
+/// XOR of all bytes between `$` and `*`, as defined by NMEA 0183.
+pub fn checksum(body: &[u8]) -> u8 {
+    body.iter().fold(0, |sum, c| sum ^ c)
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        _ => None,
+    }
+}
+
+/// Checks the `*hh` checksum of a complete `$...*hh` sentence. Trailing
+/// `\r\n` is ignored; a sentence without a checksum is rejected.
+pub fn verify_checksum(line: &[u8]) -> bool {
+    let line = trim_line_end(line);
+    if line.first() != Some(&b'$') {
+        return false;
+    }
+    match line.iter().rposition(|c| *c == b'*') {
+        Some(star) if line.len() == star + 3 => {
+            match (hex_digit(line[star + 1]), hex_digit(line[star + 2])) {
+                (Some(hi), Some(lo)) => checksum(&line[1..star]) == (hi << 4 | lo),
+                _ => false,
+            }
+        },
+        _ => false,
+    }
+}
+
+fn trim_line_end(mut line: &[u8]) -> &[u8] {
+    while let Some((b'\r', rest)) | Some((b'\n', rest)) = line.split_last() {
+        line = rest;
+    }
+    line
+}
+
+/// Two-letter talker ID in front of the sentence type, e.g. `GN` in `$GNRMC`.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum TalkerId {
+    /// GPS (and SBAS)
+    #[default]
+    GP,
+    /// GLONASS
+    GL,
+    /// Galileo
+    GA,
+    /// BeiDou, sent as `BD` or, since NMEA 4.11, `GB`
+    BD,
+    /// Combined solution from several constellations
+    GN,
+    Other([u8; 2]),
+}
+
+impl TalkerId {
+    pub fn from_bytes(id: [u8; 2]) -> Self {
+        match &id {
+            b"GP" => TalkerId::GP,
+            b"GL" => TalkerId::GL,
+            b"GA" => TalkerId::GA,
+            b"BD" | b"GB" => TalkerId::BD,
+            b"GN" => TalkerId::GN,
+            _ => TalkerId::Other(id),
+        }
+    }
+}
+
+/// Sentence type, independent of the talker.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GPS_Statement {
+    RMC,
+    GSA,
+    GGA,
+    GSV,
+    VTG,
+    GLL,
+    ZDA,
+}
+
+/// A parsed sentence of any supported type.
+#[derive(Debug, Copy, Clone)]
+pub enum Sentence {
+    RMC(RMC),
+    GSA(GSA),
+    GGA(GGA),
+    GSV(GSV),
+    VTG(VTG),
+    GLL(GLL),
+    ZDA(ZDA),
+}
+
+impl Sentence {
+    pub fn statement(&self) -> GPS_Statement {
+        match self {
+            Sentence::RMC(_) => GPS_Statement::RMC,
+            Sentence::GSA(_) => GPS_Statement::GSA,
+            Sentence::GGA(_) => GPS_Statement::GGA,
+            Sentence::GSV(_) => GPS_Statement::GSV,
+            Sentence::VTG(_) => GPS_Statement::VTG,
+            Sentence::GLL(_) => GPS_Statement::GLL,
+            Sentence::ZDA(_) => GPS_Statement::ZDA,
+        }
+    }
+    /// UTC time of the fix, for the sentences that carry one.
+    pub fn time(&self) -> Option<GPSTime> {
+        match self {
+            Sentence::RMC(data) => Some(data.time),
+            Sentence::GGA(data) => Some(data.time),
+            Sentence::GLL(data) => Some(data.time),
+            Sentence::ZDA(data) => Some(data.time),
+            _ => None,
+        }
+    }
+}
+
+/// Checks a complete `$ttsss,...*hh` sentence and splits it into talker,
+/// type and the fields following the tag, without the checksum.
+pub fn split_sentence(line: &[u8]) -> Result<(TalkerId, GPS_Statement, &[u8]), NmeaError> {
+    if !verify_checksum(line) {
+        return Err(NmeaError::ChecksumMismatch);
+    }
+    let line = trim_line_end(line);
+    let data = match line.iter().rposition(|c| *c == b'*') {
+        Some(star) => &line[..star],
+        None => line,
+    };
+    let mut msg = data.splitn(2, |c| *c == 0x2C);       // 0d44 split at ,
+    let (cmd, info) = match (msg.next(), msg.next()) {
+        (Some(cmd), Some(info)) => (cmd, info),
+        _ => return Err(NmeaError::BadFrame),
+    };
+    // `$` + talker + type; proprietary `$P...` sentences are not parsed
+    if cmd.len() != 6 || cmd[1] == b'P' {
+        return Err(NmeaError::UnknownSentence);
+    }
+    let talker = TalkerId::from_bytes([cmd[1], cmd[2]]);
+    let statement = match &cmd[3..] {
+        b"RMC" => GPS_Statement::RMC,
+        b"GSA" => GPS_Statement::GSA,
+        b"GGA" => GPS_Statement::GGA,
+        b"GSV" => GPS_Statement::GSV,
+        b"VTG" => GPS_Statement::VTG,
+        b"GLL" => GPS_Statement::GLL,
+        b"ZDA" => GPS_Statement::ZDA,
+        _ => return Err(NmeaError::UnknownSentence),
+    };
+    Ok((talker, statement, info))
+}
+
+/// Checks and parses a complete sentence.
+pub fn parse_sentence(line: &[u8]) -> Result<(TalkerId, Sentence), NmeaError> {
+    let (talker, statement, info) = split_sentence(line)?;
+    let sentence = match statement {
+        GPS_Statement::RMC => Sentence::RMC(parse_rmc(info)?),
+        GPS_Statement::GSA => Sentence::GSA(parse_gsa(info)?),
+        GPS_Statement::GGA => Sentence::GGA(parse_gga(info)?),
+        GPS_Statement::GSV => Sentence::GSV(parse_gsv(info)?),
+        GPS_Statement::VTG => Sentence::VTG(parse_vtg(info)?),
+        GPS_Statement::GLL => Sentence::GLL(parse_gll(info)?),
+        GPS_Statement::ZDA => Sentence::ZDA(parse_zda(info)?),
+    };
+    Ok((talker, sentence))
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RMC {
+    pub time: GPSTime,
+    pub valid: bool,
+    pub latitude: Coordinate,
+    pub longitude: Coordinate,
+    pub speed: Decimal,
+    pub course: Decimal,
+    pub date: GPSDate,
+    /// Degrees, easterly variation is positive. Not output by the NEO-6.
+    pub magnetic_variation: Option<Decimal>,
+    /// Missing before NMEA 2.3.
+    pub mode: Option<PositioningMode>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GGA {
+    pub position: Position,
+    pub satellites_used: u8,
+    pub fix: FixType,
+    pub time: GPSTime,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GSA {
+    pub hdop: Decimal,
+    pub vdop: Decimal,
+    pub pdop: Decimal,
+    pub fix: FixMode,
+    pub satellite_ids: [u8; 12],
+}
+
+/// One part of a GSV sequence, up to four satellites.
+#[derive(Debug, Copy, Clone)]
+pub struct GSV {
+    pub total: u8,
+    pub number: u8,
+    pub in_view: u8,
+    pub satellites: [GPSSatellite; 4],
+    pub count: usize,
+}
+
+impl GSV {
+    pub fn satellites(&self) -> &[GPSSatellite] {
+        &self.satellites[..self.count]
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct VTG {
+    pub course: Decimal,
+    /// Not output by the NEO-6.
+    pub course_magnetic: Option<Decimal>,
+    pub speed_knots: Decimal,
+    pub speed_kmh: Decimal,
+    pub mode: Option<PositioningMode>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GLL {
+    pub latitude: Coordinate,
+    pub longitude: Coordinate,
+    pub time: GPSTime,
+    pub valid: bool,
+    pub mode: Option<PositioningMode>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ZDA {
+    pub time: GPSTime,
+    pub day: u8,
+    pub month: u8,
+    /// Four-digit year.
+    pub year: u16,
+    /// Local zone offset, `hours` carries the sign.
+    pub zone_hours: i8,
+    pub zone_minutes: u8,
+}
+
+pub fn parse_rmc(data: &[u8]) -> Result<RMC, NmeaError> {
+    require_fields(data, 9)?;
+    let mut gpstime = GPSTime::new();
+    let mut gpsdate = GPSDate::new();
+    let mut validity = false;
+    let mut speed = Decimal::ZERO;
+    let mut course = Decimal::ZERO;
+    let mut lat_lon: [&[u8]; 4] = [&[]; 4];
+    let mut variation: [&[u8]; 2] = [&[]; 2];
+    let mut mode = None;
+
+    for (i, field) in data.split(|c| *c == b',').enumerate() {
+        if !field.is_empty() {
+            match i {
+                // GPSTIME
+                0 => {
+                    gpstime = parse_time(field)?;
+                },
+                // Receiver Validity
+                1 => {
+                    validity = parse_char(field)? == b'A';
+                },
+                // Latitude, N/S, longitude, E/W
+                2..=5 => {
+                    lat_lon[i - 2] = field;
+                },
+                // Speed over ground
+                6 => {
+                    speed = Decimal::parse(field)?;
+                },
+                // Course over ground
+                7 => {
+                    course = Decimal::parse(field)?;
+                },
+                // GPSDATE
+                8 => {
+                    require_len(field, 6)?;
+                    let (day, month, year) = (atoi(&field[..2])? as u8, atoi(&field[2..4])? as u8, atoi(&field[4..6])? as u8);
+                    gpsdate = GPSDate{day, month, year};
+                },
+                // Magnetic variation, E/W
+                9..=10 => {
+                    variation[i - 9] = field;
+                },
+                // Mode indicator
+                11 => {
+                    mode = Some(parse_mode(field)?);
+                },
+                _ => (),
+            }
+        }
+    }
+    let (latitude, longitude) = parse_lat_lon(lat_lon[0], lat_lon[1], lat_lon[2], lat_lon[3])?;
+    let magnetic_variation = match variation {
+        [b"", _] => None,
+        [value, b"E"] => Some(Decimal::parse(value)?),
+        [value, b"W"] => Some(-Decimal::parse(value)?),
+        [_, b""] => return Err(NmeaError::MissingField),
+        _ => return Err(NmeaError::InvalidValue),
+    };
+    Ok(RMC {
+        time: gpstime,
+        valid: validity,
+        latitude,
+        longitude,
+        speed,
+        course,
+        date: gpsdate,
+        magnetic_variation,
+        mode,
+    })
+}
+
+pub fn parse_gsa(data: &[u8]) -> Result<GSA, NmeaError> {
+    require_fields(data, 17)?;
+    let mut fixmode = FixMode::NoFix;
+    let mut sat_ids = [0u8; 12];
+    let mut hdop= Decimal::ZERO;
+    let mut vdop= Decimal::ZERO;
+    let mut pdop= Decimal::ZERO;
+
+    for (i, field) in data.split(|c| *c == b',').enumerate() {
+        if !field.is_empty() {
+            match i {
+                // Mode 1
+                0 => {
+
+                },
+                // Mode 2 - FixMode
+                1 => {
+                    fixmode = match atoi(field)? {
+                        2 => FixMode::D2,
+                        3 => FixMode::D3,
+                        _ => FixMode::NoFix,
+                    };
+                },
+                // IDs of sattelites in use
+                2..=13 => {
+                    let id = u8::try_from(atoi(field)?).map_err(|_| NmeaError::InvalidValue)?;
+                    sat_ids[i-2] = id;
+                },
+                // Position Dilution of Precision
+                14 => {
+                    pdop = Decimal::parse(field)?;
+                },
+                // Horizontal Dilution of Precision
+                15 => {
+                    hdop = Decimal::parse(field)?;
+                },
+                // Vertical Dilution of Precision
+                16 => {
+                    vdop = Decimal::parse(field)?;
+                },
+                _ => (),
+            }
+        }
+    }
+    Ok(GSA {
+        hdop,
+        vdop,
+        pdop,
+        fix: fixmode,
+        satellite_ids: sat_ids,
+    })
+}
+
+pub fn parse_gga(data: &[u8]) -> Result<GGA, NmeaError> {
+    require_fields(data, 9)?;
+    let mut gpstime = GPSTime::new();
+    let mut pos = Position::new();
+    let mut satellites = 0u8;
+    let mut fix_mode = FixType::NoFix;
+    let mut lat_lon: [&[u8]; 4] = [&[]; 4];
+
+    for (i, field) in data.split(|c| *c == b',').enumerate() {
+        if !field.is_empty() {
+            match i {
+                // UTC time
+                0 => {
+                    gpstime = parse_time(field)?;
+                },
+                // Latitude, N/S, longitude, E/W
+                1..=4 => {
+                    lat_lon[i - 1] = field;
+                },
+                // FIX
+                5 => {
+                    fix_mode = match atoi(field)? {
+                        0 => FixType::NoFix,
+                        1 => FixType::GPSFix,
+                        2 => FixType::DifferentialFix,
+                        _ => FixType::NoFix,
+                    };
+                },
+                // Satellites used
+                6 => {
+                    satellites = u8::try_from(atoi(field)?).map_err(|_| NmeaError::InvalidValue)?;
+                },
+                // Altitude
+                8 => {
+                    pos.altitude = Decimal::parse(field)?;
+                },
+                _ => (),
+            }
+        }
+    }
+
+    let (latitude, longitude) = parse_lat_lon(lat_lon[0], lat_lon[1], lat_lon[2], lat_lon[3])?;
+    pos.latitude = latitude;
+    pos.longitude = longitude;
+
+    Ok(GGA {
+        time: gpstime,
+        position: pos,
+        satellites_used: satellites,
+        fix: fix_mode,
+    })
+}
+
+fn parse_optional(field: &[u8], max: u32) -> Result<Option<u32>, NmeaError> {
+    if field.is_empty() {
+        return Ok(None);
+    }
+    let value = atoi(field)?;
+    if value > max {
+        return Err(NmeaError::InvalidValue);
+    }
+    Ok(Some(value))
+}
+
+pub fn parse_gsv(data: &[u8]) -> Result<GSV, NmeaError> {
+    require_fields(data, 3)?;
+    let mut fields = data.split(|c| *c == b',');
+    let mut header = [0u8; 3];
+    for value in header.iter_mut() {
+        *value = parse_optional(fields.next().unwrap_or(&[]), 255)?.ok_or(NmeaError::MissingField)? as u8;
+    }
+    let [total, number, in_view] = header;
+    if number == 0 || number > total {
+        return Err(NmeaError::InvalidValue);
+    }
+
+    let mut satellites = [GPSSatellite::default(); 4];
+    let mut count = 0;
+    while count < 4 {
+        let prn = match fields.next() {
+            Some(field) if !field.is_empty() => atoi(field)?,
+            // last part carries fewer than four satellites
+            _ => break,
+        };
+        let elevation = parse_optional(fields.next().ok_or(NmeaError::MissingField)?, 90)?;
+        let azimuth = parse_optional(fields.next().ok_or(NmeaError::MissingField)?, 359)?;
+        let snr = parse_optional(fields.next().ok_or(NmeaError::MissingField)?, 99)?;
+        if prn == 0 || prn > 255 {
+            return Err(NmeaError::InvalidValue);
+        }
+        satellites[count] = GPSSatellite {
+            talker: TalkerId::default(),
+            prn: prn as u8,
+            elevation: elevation.map(|e| e as u8),
+            azimuth: azimuth.map(|a| a as u16),
+            snr: snr.map(|s| s as u8),
+            used: false,
+        };
+        count += 1;
+    }
+
+    Ok(GSV {
+        total,
+        number,
+        in_view,
+        satellites,
+        count,
+    })
+}
+
+pub fn parse_vtg(data: &[u8]) -> Result<VTG, NmeaError> {
+    require_fields(data, 8)?;
+    let mut vtg = VTG {
+        course: Decimal::ZERO,
+        course_magnetic: None,
+        speed_knots: Decimal::ZERO,
+        speed_kmh: Decimal::ZERO,
+        mode: None,
+    };
+
+    for (i, field) in data.split(|c| *c == b',').enumerate() {
+        if !field.is_empty() {
+            match i {
+                // Course over ground, true
+                0 => vtg.course = Decimal::parse(field)?,
+                // Course over ground, magnetic
+                2 => vtg.course_magnetic = Some(Decimal::parse(field)?),
+                // Speed over ground, knots
+                4 => vtg.speed_knots = Decimal::parse(field)?,
+                // Speed over ground, km/h
+                6 => vtg.speed_kmh = Decimal::parse(field)?,
+                // Mode indicator
+                8 => vtg.mode = Some(parse_mode(field)?),
+                // Unit fields T, M, N, K
+                _ => (),
+            }
+        }
+    }
+    Ok(vtg)
+}
+
+pub fn parse_gll(data: &[u8]) -> Result<GLL, NmeaError> {
+    require_fields(data, 6)?;
+    let mut lat_lon: [&[u8]; 4] = [&[]; 4];
+    let mut time = GPSTime::new();
+    let mut valid = false;
+    let mut mode = None;
+
+    for (i, field) in data.split(|c| *c == b',').enumerate() {
+        if !field.is_empty() {
+            match i {
+                // Latitude, N/S, longitude, E/W
+                0..=3 => lat_lon[i] = field,
+                // UTC time
+                4 => time = parse_time(field)?,
+                // Status
+                5 => valid = parse_char(field)? == b'A',
+                // Mode indicator
+                6 => mode = Some(parse_mode(field)?),
+                _ => (),
+            }
+        }
+    }
+    let (latitude, longitude) = parse_lat_lon(lat_lon[0], lat_lon[1], lat_lon[2], lat_lon[3])?;
+    Ok(GLL {
+        latitude,
+        longitude,
+        time,
+        valid,
+        mode,
+    })
+}
+
+pub fn parse_zda(data: &[u8]) -> Result<ZDA, NmeaError> {
+    require_fields(data, 6)?;
+    let mut fields = data.split(|c| *c == b',');
+    let mut next = || fields.next().filter(|f| !f.is_empty()).ok_or(NmeaError::MissingField);
+
+    let time = parse_time(next()?)?;
+    let day = atoi(next()?)?;
+    let month = atoi(next()?)?;
+    let year = next()?;
+    require_len(year, 4)?;
+    let year = atoi(year)?;
+    let zone_hours = Decimal::parse(next()?)?;
+    let zone_minutes = atoi(next()?)?;
+    if day == 0 || day > 31 || month == 0 || month > 12 || year > 9999
+        || zone_hours.scale() != 0 || zone_hours.value().abs() > 13 || zone_minutes > 59 {
+        return Err(NmeaError::InvalidValue);
+    }
+    Ok(ZDA {
+        time,
+        day: day as u8,
+        month: month as u8,
+        year: year as u16,
+        zone_hours: zone_hours.value() as i8,
+        zone_minutes: zone_minutes as u8,
+    })
+}
@@ -0,0 +1,180 @@
+//! PPS time pulse, for timing finer than the NMEA millisecond.
+//!
+//! The NEO-6 timepulse output rises at the start of each UTC second, before
+//! the sentences carrying that second. `Pps` takes the MCU timestamp of each
+//! rising edge, e.g. a timer input capture extended to 32 bits, labels it
+//! with the time of the next RMC or ZDA, and measures the MCU clock against
+//! the interval between labelled edges.
+//!
+//! Timestamps are free-running counts that wrap; the holdover has to stay
+//! well below the wrap period, 59 s for a 72 MHz cycle counter.
+
+use crate::time::UtcDateTime;
+
+/// Default for `Pps::set_holdover`, in seconds.
+pub const DEFAULT_HOLDOVER: u32 = 10;
+/// Edges implying a larger frequency error than this are taken for
+/// glitches and dropped.
+pub const MAX_OFFSET_PPM: i64 = 500;
+
+/// Unix time from the PPS, to the nanosecond of the MCU clock.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PpsTime {
+    pub seconds: i64,
+    pub nanos: u32,
+}
+
+impl PpsTime {
+    pub fn unix_micros(&self) -> i64 {
+        self.seconds * 1_000_000 + (self.nanos / 1000) as i64
+    }
+    pub fn utc(&self) -> Option<UtcDateTime> {
+        UtcDateTime::from_unix_millis(self.seconds * 1000 + (self.nanos / 1_000_000) as i64)
+    }
+}
+
+// labelled edge
+#[derive(Copy, Clone)]
+struct Edge {
+    count: u32,
+    second: i64,
+}
+
+pub struct Pps {
+    nominal: u32,
+    holdover: u32,
+    // edge waiting for its time
+    pending: Option<u32>,
+    last: Option<Edge>,
+    // counts per GPS second, 0 until measured
+    frequency: u32,
+    offset: Option<i32>,
+    drift: Option<i32>,
+    edges: u32,
+    rejected: u32,
+}
+
+impl Pps {
+    pub fn new() -> Self {
+        Pps {
+            nominal: 0,
+            holdover: DEFAULT_HOLDOVER,
+            pending: None,
+            last: None,
+            frequency: 0,
+            offset: None,
+            drift: None,
+            edges: 0,
+            rejected: 0,
+        }
+    }
+    /// Nominal rate of the timestamp counter, in Hz. Needed for the offset
+    /// and to reject glitches; 0, the default, leaves both out.
+    pub fn set_nominal(&mut self, hz: u32) {
+        self.nominal = hz;
+    }
+    /// Seconds after the last labelled edge `now()` keeps counting from it.
+    pub fn set_holdover(&mut self, seconds: u32) {
+        self.holdover = seconds;
+    }
+    /// Timestamp of a rising edge; replaces an edge not labelled yet.
+    pub fn capture(&mut self, count: u32) {
+        self.pending = Some(count);
+    }
+    /// Labels the pending edge with the UTC second `utc` starts. Times off the
+    /// second, as from navigation rates above 1 Hz, leave the edge pending.
+    /// Returns whether an edge was labelled.
+    pub fn label(&mut self, utc: UtcDateTime) -> bool {
+        if utc.millisecond != 0 {
+            return false;
+        }
+        let count = match self.pending.take() {
+            Some(count) => count,
+            None => return false,
+        };
+        let edge = Edge { count, second: utc.unix_seconds() };
+        if let Some(last) = self.last {
+            let seconds = edge.second - last.second;
+            if seconds <= 0 {
+                self.rejected += 1;
+                return false;
+            }
+            if seconds <= self.holdover as i64 && !self.measure(seconds, count.wrapping_sub(last.count)) {
+                self.rejected += 1;
+                return false;
+            }
+        }
+        self.last = Some(edge);
+        self.edges += 1;
+        true
+    }
+    // updates the rate from `elapsed` counts over `seconds`, false if the
+    // offset is implausible
+    fn measure(&mut self, seconds: i64, elapsed: u32) -> bool {
+        if self.nominal != 0 {
+            let expected = self.nominal as i64 * seconds;
+            let offset = (elapsed as i64 - expected) * 1_000_000_000 / expected;
+            if offset.abs() > MAX_OFFSET_PPM * 1000 {
+                return false;
+            }
+            let offset = offset as i32;
+            self.drift = self.offset.map(|last| (offset - last) / seconds as i32);
+            self.offset = Some(offset);
+        }
+        self.frequency = (elapsed as i64 / seconds) as u32;
+        true
+    }
+    /// Time at the counter value `count`, `None` without a labelled edge,
+    /// without a known rate or past the holdover.
+    pub fn now(&self, count: u32) -> Option<PpsTime> {
+        let last = self.last?;
+        let frequency = match self.frequency() {
+            Some(frequency) => frequency as u64,
+            None if self.nominal != 0 => self.nominal as u64,
+            None => return None,
+        };
+        let elapsed = count.wrapping_sub(last.count) as u64;
+        let seconds = elapsed / frequency;
+        if seconds >= self.holdover as u64 {
+            return None;
+        }
+        let nanos = (elapsed % frequency) * 1_000_000_000 / frequency;
+        Some(PpsTime { seconds: last.second + seconds as i64, nanos: nanos as u32 })
+    }
+    /// Measured counts per second.
+    pub fn frequency(&self) -> Option<u32> {
+        if self.frequency == 0 {
+            None
+        } else {
+            Some(self.frequency)
+        }
+    }
+    /// Error of the counter rate against the nominal, in parts per billion;
+    /// positive if it runs fast.
+    pub fn offset_ppb(&self) -> Option<i32> {
+        self.offset
+    }
+    /// Change of the offset between the last two measurements, in parts per
+    /// billion per second.
+    pub fn drift_ppb(&self) -> Option<i32> {
+        self.drift
+    }
+    /// Count and Unix second of the last labelled edge.
+    pub fn last_edge(&self) -> Option<(u32, i64)> {
+        self.last.map(|edge| (edge.count, edge.second))
+    }
+    /// Labelled edges so far.
+    pub fn edges(&self) -> u32 {
+        self.edges
+    }
+    /// Edges dropped as glitches or labelled out of order.
+    pub fn rejected(&self) -> u32 {
+        self.rejected
+    }
+}
+
+impl Default for Pps {
+    fn default() -> Self {
+        Pps::new()
+    }
+}
@@ -0,0 +1,223 @@
+//! Lock-free single-producer/single-consumer byte queue between the serial
+//! RX interrupt and the main loop.
+//!
+//! The interrupt owns the `Producer` and pushes every received byte; the
+//! `NEO6` driver reads from the `Consumer`, which implements the serial
+//! `Read` trait. Neither side ever waits for the other: when the queue is
+//! full new bytes are dropped and counted, and the consumer reports
+//! `LineError::QueueOverflow` where they are missing.
+//!
+//! UART receive errors travel with the stream: the consumer reports them
+//! from `read()` at the position they occurred, so the driver knows which
+//! sentence they broke.
+
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU8, AtomicU32, AtomicUsize, Ordering};
+
+use embedded_hal::serial::Read;
+
+use crate::error::{LineError, ReadError};
+
+/// Ring buffer over a caller-provided slice; holds `len - 1` bytes.
+pub struct RingBuffer<'a> {
+    buffer: *mut u8,
+    len: usize,
+    /// Next slot written by the producer.
+    head: AtomicUsize,
+    /// Next slot read by the consumer.
+    tail: AtomicUsize,
+    overflows: AtomicU32,
+    /// Slot plus one the pending receive error was raised before, 0 if none.
+    error_at: AtomicUsize,
+    error: AtomicU8,
+    _buffer: PhantomData<&'a mut [u8]>,
+}
+
+// The producer only writes `head` and free slots, the consumer only `tail`
+// and filled slots; ownership of a slot passes with the release store.
+unsafe impl<'a> Sync for RingBuffer<'a> {}
+unsafe impl<'a> Send for RingBuffer<'a> {}
+
+impl<'a> RingBuffer<'a> {
+    /// `buf` must hold at least two bytes.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        assert!(buf.len() >= 2);
+        RingBuffer {
+            buffer: buf.as_mut_ptr(),
+            len: buf.len(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            overflows: AtomicU32::new(0),
+            error_at: AtomicUsize::new(0),
+            error: AtomicU8::new(0),
+            _buffer: PhantomData,
+        }
+    }
+    /// Splits into the interrupt and main-loop halves.
+    pub fn split<'r>(&'r mut self) -> (Producer<'r, 'a>, Consumer<'r, 'a>) {
+        (Producer { ring: self }, Consumer { ring: self })
+    }
+    pub fn capacity(&self) -> usize {
+        self.len - 1
+    }
+    /// Bytes waiting to be read.
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (head + self.len - tail) % self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Bytes dropped because the queue was full.
+    pub fn overflows(&self) -> u32 {
+        self.overflows.load(Ordering::Relaxed)
+    }
+    /// Room left for new bytes.
+    pub fn free(&self) -> usize {
+        self.capacity() - self.len()
+    }
+    /// Queues a byte when the buffer is not split; `false` if it is full.
+    pub fn push(&mut self, byte: u8) -> bool {
+        self.enqueue(byte)
+    }
+    pub fn pop(&mut self) -> Option<u8> {
+        self.dequeue()
+    }
+    /// Next byte `pop()` would return.
+    pub fn peek(&self) -> Option<u8> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail == self.head.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(unsafe { self.slot(tail).read() })
+    }
+    fn slot(&self, index: usize) -> *mut u8 {
+        unsafe { self.buffer.add(index) }
+    }
+    // producer side
+    fn enqueue(&self, byte: u8) -> bool {
+        let head = self.head.load(Ordering::Relaxed);
+        let next = (head + 1) % self.len;
+        if next == self.tail.load(Ordering::Acquire) {
+            // only the producer writes the counter, no read-modify-write needed
+            self.overflows.store(self.overflows.load(Ordering::Relaxed).wrapping_add(1), Ordering::Relaxed);
+            // the gap is reported in place like a receive error, or lost
+            // like one while another is still pending
+            self.raise(LineError::QueueOverflow);
+            return false;
+        }
+        unsafe { self.slot(head).write(byte) };
+        self.head.store(next, Ordering::Release);
+        true
+    }
+    fn raise(&self, error: LineError) -> bool {
+        if self.error_at.load(Ordering::Acquire) != 0 {
+            return false;
+        }
+        self.error.store(error as u8, Ordering::Relaxed);
+        self.error_at.store(self.head.load(Ordering::Relaxed) + 1, Ordering::Release);
+        true
+    }
+    // consumer side
+    fn take_error(&self) -> Option<LineError> {
+        let at = self.error_at.load(Ordering::Acquire);
+        if at == 0 || at - 1 != self.tail.load(Ordering::Relaxed) {
+            return None;
+        }
+        let error = match self.error.load(Ordering::Relaxed) {
+            e if e == LineError::Overrun as u8 => LineError::Overrun,
+            e if e == LineError::Framing as u8 => LineError::Framing,
+            e if e == LineError::Noise as u8 => LineError::Noise,
+            e if e == LineError::Parity as u8 => LineError::Parity,
+            _ => LineError::QueueOverflow,
+        };
+        self.error_at.store(0, Ordering::Release);
+        Some(error)
+    }
+    fn dequeue(&self) -> Option<u8> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail == self.head.load(Ordering::Acquire) {
+            return None;
+        }
+        let byte = unsafe { self.slot(tail).read() };
+        self.tail.store((tail + 1) % self.len, Ordering::Release);
+        Some(byte)
+    }
+}
+
+/// Writing half, owned by the RX interrupt.
+pub struct Producer<'r, 'a> {
+    ring: &'r RingBuffer<'a>,
+}
+
+impl<'r, 'a> Producer<'r, 'a> {
+    /// Queues a byte; `false` if the queue is full and the byte was dropped.
+    pub fn push(&mut self, byte: u8) -> bool {
+        self.ring.enqueue(byte)
+    }
+    /// Records a receive error at the current position of the stream.
+    /// `false` if the consumer has not yet read up to the previous error,
+    /// in which case this one is dropped.
+    pub fn push_error(&mut self, error: LineError) -> bool {
+        self.ring.raise(error)
+    }
+    /// Moves every byte available from `rx` into the queue, along with the
+    /// receive errors between them.
+    pub fn receive<Rx>(&mut self, rx: &mut Rx)
+    where
+        Rx: Read<u8>,
+        Rx::Error: ReadError,
+    {
+        loop {
+            match rx.read() {
+                Ok(byte) => {
+                    self.push(byte);
+                },
+                Err(nb::Error::Other(e)) => {
+                    if let Some(error) = e.line_error() {
+                        self.push_error(error);
+                    }
+                },
+                Err(nb::Error::WouldBlock) => break,
+            }
+        }
+    }
+    pub fn overflows(&self) -> u32 {
+        self.ring.overflows()
+    }
+}
+
+/// Reading half, owned by the main loop.
+pub struct Consumer<'r, 'a> {
+    ring: &'r RingBuffer<'a>,
+}
+
+impl<'r, 'a> Consumer<'r, 'a> {
+    /// Next byte, skipping over receive errors.
+    pub fn pop(&mut self) -> Option<u8> {
+        self.ring.take_error();
+        self.ring.dequeue()
+    }
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+    /// Bytes dropped by the producer because the queue was full.
+    pub fn overflows(&self) -> u32 {
+        self.ring.overflows()
+    }
+}
+
+impl<'r, 'a> Read<u8> for Consumer<'r, 'a> {
+    type Error = LineError;
+    /// Returns a receive error once the bytes received before it are read.
+    fn read(&mut self) -> nb::Result<u8, LineError> {
+        if let Some(error) = self.ring.take_error() {
+            return Err(nb::Error::Other(error));
+        }
+        self.ring.dequeue().ok_or(nb::Error::WouldBlock)
+    }
+}
@@ -0,0 +1,113 @@
+//! Keeps a real-time clock set from GPS time.
+//!
+//! The clock counts whole seconds of Unix time, like the STM32F1 RTC with a
+//! 1 Hz prescaler, and keeps time while the sky is not visible. `RtcSync`
+//! sets it from the first epoch with a fix and time, then again every sync
+//! interval; at each later sync the error the clock has built up gives its
+//! drift. With a one-second count the drift resolves one second per
+//! interval, 278 ppm over the default hour, so longer intervals measure it
+//! finer.
+
+use crate::data::GPS_Data;
+use crate::time::UtcDateTime;
+
+/// Default for `RtcSync::set_interval`, in seconds.
+pub const DEFAULT_INTERVAL: u32 = 3600;
+/// A clock further off GPS time than this rate allows is taken to have been
+/// reset, or GPS time to have jumped, rather than to have drifted.
+pub const MAX_DRIFT_PPM: i64 = 1000;
+
+/// Clock counting seconds since 1970-01-01T00:00:00Z.
+pub trait RealTimeClock {
+    fn seconds(&self) -> u32;
+    fn set_seconds(&mut self, seconds: u32);
+    /// Time of the clock, `None` if it holds no valid date.
+    fn utc(&self) -> Option<UtcDateTime> {
+        UtcDateTime::from_unix_seconds(self.seconds() as i64)
+    }
+}
+
+pub struct RtcSync {
+    interval: u32,
+    // GPS time the clock was last set to
+    last_sync: Option<u32>,
+    // clock minus GPS time, found at the last sync before setting
+    offset: Option<i32>,
+    drift: Option<i32>,
+    syncs: u32,
+}
+
+impl RtcSync {
+    pub fn new() -> Self {
+        RtcSync {
+            interval: DEFAULT_INTERVAL,
+            last_sync: None,
+            offset: None,
+            drift: None,
+            syncs: 0,
+        }
+    }
+    /// Seconds between syncs.
+    pub fn set_interval(&mut self, seconds: u32) {
+        self.interval = seconds;
+    }
+    /// Sets `rtc` from `data` if it has a fix with date and time and the
+    /// clock is due for a sync. GPS time going back, or a clock off by more
+    /// than `MAX_DRIFT_PPM` allows, sets it at once and starts the drift
+    /// measurement over. Returns whether the clock was set.
+    pub fn update<R: RealTimeClock>(&mut self, rtc: &mut R, data: &GPS_Data) -> bool {
+        if !data.has_fix() {
+            return false;
+        }
+        let gps = match data.get_utc().map(|utc| utc.unix_seconds()) {
+            Some(seconds) if (0..=u32::MAX as i64).contains(&seconds) => seconds as u32,
+            _ => return false,
+        };
+        if let Some(last) = self.last_sync {
+            let elapsed = gps as i64 - last as i64;
+            if elapsed >= 0 && elapsed < self.interval as i64 {
+                return false;
+            }
+            let offset = rtc.seconds() as i64 - gps as i64;
+            if elapsed > 0 && offset.abs() * 1_000_000 <= MAX_DRIFT_PPM * elapsed {
+                self.offset = Some(offset as i32);
+                self.drift = Some((offset * 1_000_000 / elapsed) as i32);
+            } else {
+                self.offset = None;
+                self.drift = None;
+            }
+        }
+        rtc.set_seconds(gps);
+        self.last_sync = Some(gps);
+        self.syncs += 1;
+        true
+    }
+    /// Seconds since the clock was last set, by the clock itself; `None`
+    /// before the first sync.
+    pub fn since_sync<R: RealTimeClock>(&self, rtc: &R) -> Option<u32> {
+        self.last_sync.map(|last| rtc.seconds().wrapping_sub(last))
+    }
+    /// GPS time the clock was last set to, in Unix seconds.
+    pub fn last_sync(&self) -> Option<u32> {
+        self.last_sync
+    }
+    /// Clock minus GPS time at the last sync, before it was corrected.
+    /// Positive if the clock ran fast.
+    pub fn offset(&self) -> Option<i32> {
+        self.offset
+    }
+    /// Rate the clock gained over the last sync interval, in parts per
+    /// million; negative if it lost time.
+    pub fn drift_ppm(&self) -> Option<i32> {
+        self.drift
+    }
+    pub fn syncs(&self) -> u32 {
+        self.syncs
+    }
+}
+
+impl Default for RtcSync {
+    fn default() -> Self {
+        RtcSync::new()
+    }
+}
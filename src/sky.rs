@@ -0,0 +1,106 @@
+use crate::error::NmeaError;
+use crate::nmea::{GSV, TalkerId};
+use crate::types::GPSSatellite;
+
+/// Capacity of the sky view table, enough for GPS, SBAS and GLONASS together.
+pub const MAX_SATELLITES: usize = 32;
+
+/// Satellites in view, as reported by the latest complete GSV sequence of
+/// each talker.
+#[derive(Debug, Copy, Clone)]
+pub struct SkyView {
+    satellites: [GPSSatellite; MAX_SATELLITES],
+    len: usize,
+}
+
+impl SkyView {
+    pub fn new() -> Self {
+        SkyView {
+            satellites: [GPSSatellite::default(); MAX_SATELLITES],
+            len: 0,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn satellites(&self) -> &[GPSSatellite] {
+        &self.satellites[..self.len]
+    }
+    pub fn get(&self, prn: u8) -> Option<&GPSSatellite> {
+        self.satellites().iter().find(|sat| sat.prn == prn)
+    }
+    /// Satellites the receiver is tracking, i.e. with a C/N0 reading.
+    pub fn tracked(&self) -> usize {
+        self.satellites().iter().filter(|sat| sat.snr.is_some()).count()
+    }
+    /// Adds a satellite, silently ignored when the table is full.
+    pub fn push(&mut self, satellite: GPSSatellite) {
+        if self.len < MAX_SATELLITES {
+            self.satellites[self.len] = satellite;
+            self.len += 1;
+        }
+    }
+    /// Replaces the satellites of the talkers present in `other` by those of
+    /// `other`, keeping the other constellations.
+    pub fn merge(&mut self, other: &SkyView) {
+        let mut merged = SkyView::new();
+        for sat in self.satellites() {
+            if !other.satellites().iter().any(|o| o.talker == sat.talker) {
+                merged.push(*sat);
+            }
+        }
+        for sat in other.satellites() {
+            merged.push(*sat);
+        }
+        *self = merged;
+    }
+    /// Sets the `used` flag from the GSA list of satellites used in the fix.
+    pub fn mark_used(&mut self, satellite_ids: &[u8]) {
+        for sat in self.satellites[..self.len].iter_mut() {
+            sat.used = satellite_ids.contains(&sat.prn);
+        }
+    }
+}
+
+impl Default for SkyView {
+    fn default() -> Self {
+        SkyView::new()
+    }
+}
+
+/// Collects the parts of a GSV sequence into a `SkyView`.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct GsvAssembler {
+    pending: SkyView,
+    talker: TalkerId,
+    next: u8,
+}
+
+impl GsvAssembler {
+    pub fn new() -> Self {
+        GsvAssembler::default()
+    }
+    /// Adds one GSV part and returns the sky view of `talker` once the last
+    /// part of the sequence arrives. A part out of order discards the sequence.
+    pub fn add(&mut self, talker: TalkerId, gsv: &GSV) -> Result<Option<SkyView>, NmeaError> {
+        if gsv.number == 1 {
+            self.pending = SkyView::new();
+            self.talker = talker;
+        } else if gsv.number != self.next || talker != self.talker {
+            self.next = 0;
+            return Err(NmeaError::BadSequence);
+        }
+        for sat in gsv.satellites() {
+            self.pending.push(GPSSatellite { talker, ..*sat });
+        }
+        if gsv.number == gsv.total {
+            self.next = 0;
+            return Ok(Some(self.pending));
+        }
+        self.next = gsv.number + 1;
+        Ok(None)
+    }
+}
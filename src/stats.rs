@@ -0,0 +1,84 @@
+use crate::error::{NmeaError, UbxError, LineError};
+
+/// Receive statistics kept by the `NEO6` driver.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Statistics {
+    /// Bytes received, valid or not.
+    pub bytes: u32,
+    /// Sentences parsed successfully.
+    pub sentences: u32,
+    /// Sentences dropped because of a missing or wrong `*hh` checksum.
+    pub checksum_errors: u32,
+    /// Valid sentences of a type the driver does not parse.
+    pub unknown_sentences: u32,
+    /// Sentences dropped because of malformed or missing fields.
+    pub parse_errors: u32,
+    /// Lines dropped because they did not fit the line buffer.
+    pub overlong_lines: u32,
+    /// Most recent error of any kind.
+    pub last_error: Option<NmeaError>,
+    /// UBX frames decoded successfully.
+    pub ubx_frames: u32,
+    /// UBX frames dropped because of a bad checksum, length or overflow, and
+    /// false syncs.
+    pub ubx_errors: u32,
+    /// Valid UBX frames of a class and id the driver does not decode.
+    pub ubx_unknown: u32,
+    /// Most recent UBX error.
+    pub last_ubx_error: Option<UbxError>,
+    /// Sentences the NMEA tap dropped because its queue was full.
+    pub tap_dropped: u32,
+    /// UART overruns, bytes lost because they were not read in time.
+    pub overruns: u32,
+    /// UART framing errors, usually a baud rate mismatch.
+    pub framing_errors: u32,
+    /// Bytes received with noise on the line.
+    pub noise_errors: u32,
+    /// Bytes received with a wrong parity bit.
+    pub parity_errors: u32,
+    /// Times the receive queue was full and dropped bytes, see
+    /// `queue::Consumer::overflows` for the bytes.
+    pub queue_overflows: u32,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics::default()
+    }
+    pub fn count_error(&mut self, error: NmeaError) {
+        match error {
+            NmeaError::ChecksumMismatch => self.checksum_errors += 1,
+            NmeaError::UnknownSentence => self.unknown_sentences += 1,
+            NmeaError::LineTooLong => self.overlong_lines += 1,
+            _ => self.parse_errors += 1,
+        }
+        self.last_error = Some(error);
+    }
+    pub fn count_ubx_error(&mut self, error: UbxError) {
+        match error {
+            UbxError::UnknownMessage => self.ubx_unknown += 1,
+            _ => self.ubx_errors += 1,
+        }
+        self.last_ubx_error = Some(error);
+    }
+    pub fn count_line_error(&mut self, error: LineError) {
+        match error {
+            LineError::Overrun => self.overruns += 1,
+            LineError::Framing => self.framing_errors += 1,
+            LineError::Noise => self.noise_errors += 1,
+            LineError::Parity => self.parity_errors += 1,
+            LineError::QueueOverflow => self.queue_overflows += 1,
+        }
+    }
+    /// Receive errors of any kind, from the UART or the queue.
+    pub fn line_errors(&self) -> u32 {
+        self.overruns + self.framing_errors + self.noise_errors + self.parity_errors + self.queue_overflows
+    }
+    /// Valid sentences and UBX frames, whether the driver decodes them or not.
+    pub fn received(&self) -> u32 {
+        self.sentences + self.unknown_sentences + self.ubx_frames + self.ubx_unknown
+    }
+    pub fn errors(&self) -> u32 {
+        self.checksum_errors + self.parse_errors + self.overlong_lines + self.ubx_errors + self.line_errors()
+    }
+}
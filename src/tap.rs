@@ -0,0 +1,119 @@
+//! Copy of the received NMEA stream for logging.
+//!
+//! Sentences are queued whole and written out with non-blocking writes, so
+//! the tap never stalls reception; when the queue is full the sentence is
+//! dropped and counted.
+
+use embedded_hal::serial::Write;
+
+use crate::nmea::{GPS_Statement, split_sentence};
+use crate::queue::RingBuffer;
+
+/// Set of sentence types.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct SentenceSet(u8);
+
+impl SentenceSet {
+    pub const EMPTY: SentenceSet = SentenceSet(0);
+
+    pub const fn with(self, statement: GPS_Statement) -> Self {
+        SentenceSet(self.0 | SentenceSet::bit(statement))
+    }
+    pub fn contains(&self, statement: GPS_Statement) -> bool {
+        self.0 & SentenceSet::bit(statement) != 0
+    }
+    /// Every sentence type of `other` is in the set.
+    pub fn contains_all(&self, other: SentenceSet) -> bool {
+        self.0 & other.0 == other.0
+    }
+    const fn bit(statement: GPS_Statement) -> u8 {
+        1 << statement as u8
+    }
+}
+
+/// What the tap passes through.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TapMode {
+    Off,
+    /// Every NMEA line as received, including ones failing the checksum.
+    Raw,
+    /// Only valid sentences of the given types.
+    Filtered(SentenceSet),
+}
+
+pub struct Tap<'a> {
+    mode: TapMode,
+    queue: Option<RingBuffer<'a>>,
+    dropped: u32,
+}
+
+impl<'a> Tap<'a> {
+    pub fn new() -> Self {
+        Tap {
+            mode: TapMode::Off,
+            queue: None,
+            dropped: 0,
+        }
+    }
+    /// Enables the tap, queueing output in `buffer`.
+    pub fn set(&mut self, mode: TapMode, buffer: &'a mut [u8]) {
+        self.mode = mode;
+        self.queue = Some(RingBuffer::new(buffer));
+    }
+    /// Changes the mode, keeping the buffer.
+    pub fn set_mode(&mut self, mode: TapMode) {
+        self.mode = mode;
+    }
+    pub fn mode(&self) -> TapMode {
+        self.mode
+    }
+    /// Sentences dropped because the queue was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+    pub fn is_empty(&self) -> bool {
+        match &self.queue {
+            Some(queue) => queue.is_empty(),
+            None => true,
+        }
+    }
+    /// Queues a line, given without its line ending, if the mode passes it.
+    pub fn write_line(&mut self, line: &[u8]) {
+        let pass = match self.mode {
+            TapMode::Off => false,
+            TapMode::Raw => true,
+            TapMode::Filtered(set) => match split_sentence(line) {
+                Ok((_, statement, _)) => set.contains(statement),
+                Err(_) => false,
+            },
+        };
+        let queue = match self.queue.as_mut() {
+            Some(queue) if pass => queue,
+            _ => return,
+        };
+        if queue.free() < line.len() + 2 {
+            self.dropped += 1;
+            return;
+        }
+        for byte in line.iter().chain(b"\r\n") {
+            queue.push(*byte);
+        }
+    }
+    /// Writes queued bytes until `tx` would block.
+    pub fn flush<Tx: Write<u8>>(&mut self, tx: &mut Tx) {
+        if let Some(queue) = self.queue.as_mut() {
+            while let Some(byte) = queue.peek() {
+                if tx.write(byte).is_err() {
+                    break;
+                }
+                queue.pop();
+            }
+        }
+    }
+}
+
+impl<'a> Default for Tap<'a> {
+    fn default() -> Self {
+        Tap::new()
+    }
+}
@@ -0,0 +1,189 @@
+//! UTC date and time to the millisecond, for timestamping records.
+//!
+//! Calendar arithmetic follows the proleptic Gregorian calendar; leap
+//! seconds are accepted as second 60 but, as in Unix time, not counted.
+
+use core::fmt;
+
+use crate::types::{GPSDate, GPSTime};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Weekday {
+    Monday = 1,
+    Tuesday = 2,
+    Wednesday = 3,
+    Thursday = 4,
+    Friday = 5,
+    Saturday = 6,
+    Sunday = 7,
+}
+
+impl Weekday {
+    /// From days since Monday, 0-6.
+    fn from_index(index: u8) -> Self {
+        match index {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+// `is_multiple_of` needs Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+pub fn is_leap_year(year: u16) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Days in `month` (1-12) of `year`, 0 for an invalid month.
+pub fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Date and time in UTC, ordered chronologically.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UtcDateTime {
+    /// Four-digit year.
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    /// 0-60, 60 being a leap second.
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+impl UtcDateTime {
+    /// `None` unless the fields form a valid date and time in years 0-9999.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8, millisecond: u16) -> Option<Self> {
+        let utc = UtcDateTime { year, month, day, hour, minute, second, millisecond };
+        if utc.is_valid() {
+            Some(utc)
+        } else {
+            None
+        }
+    }
+    /// Combines a NMEA date and time. The year is the first one ending in
+    /// the two digits of `date` from `full_year` (ZDA) on, so a date past New
+    /// Year moves on from the last ZDA; without it two-digit years are taken
+    /// as 1980-2079, the GPS era.
+    pub fn from_gps(date: GPSDate, time: GPSTime, full_year: Option<u16>) -> Option<Self> {
+        let year = match full_year {
+            Some(year) => {
+                let same_century = year - year % 100 + date.year as u16;
+                if same_century < year {
+                    same_century + 100
+                } else {
+                    same_century
+                }
+            },
+            None if date.year >= 80 => 1900 + date.year as u16,
+            None => 2000 + date.year as u16,
+        };
+        UtcDateTime::new(year, date.month, date.day, time.hour, time.minute, time.second, time.millisecond)
+    }
+    pub fn is_valid(&self) -> bool {
+        self.year <= 9999
+            && self.day >= 1
+            && self.day <= days_in_month(self.year, self.month)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second <= 60
+            && self.millisecond < 1000
+    }
+    pub fn date(&self) -> GPSDate {
+        GPSDate { day: self.day, month: self.month, year: (self.year % 100) as u8 }
+    }
+    pub fn time(&self) -> GPSTime {
+        GPSTime { hour: self.hour, minute: self.minute, second: self.second, millisecond: self.millisecond }
+    }
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        Weekday::from_index((self.days_since_epoch() + 3).rem_euclid(7) as u8)
+    }
+    /// Day of the year, 1-366.
+    pub fn day_of_year(&self) -> u16 {
+        let before: u16 = (1..self.month).map(|month| days_in_month(self.year, month) as u16).sum();
+        before + self.day as u16
+    }
+    /// Seconds since 1970-01-01T00:00:00Z, negative before.
+    pub fn unix_seconds(&self) -> i64 {
+        let seconds = self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        self.days_since_epoch() * SECONDS_PER_DAY + seconds
+    }
+    pub fn unix_millis(&self) -> i64 {
+        self.unix_seconds() * 1000 + self.millisecond as i64
+    }
+    /// `None` outside years 0-9999.
+    pub fn from_unix_seconds(seconds: i64) -> Option<Self> {
+        UtcDateTime::from_unix_millis(seconds.checked_mul(1000)?)
+    }
+    pub fn from_unix_millis(millis: i64) -> Option<Self> {
+        let days = millis.div_euclid(SECONDS_PER_DAY * 1000);
+        let rest = millis.rem_euclid(SECONDS_PER_DAY * 1000);
+        let (year, month, day) = civil_from_days(days);
+        if !(0..=9999).contains(&year) {
+            return None;
+        }
+        let seconds = rest / 1000;
+        UtcDateTime::new(
+            year as u16,
+            month,
+            day,
+            (seconds / 3600) as u8,
+            (seconds / 60 % 60) as u8,
+            (seconds % 60) as u8,
+            (rest % 1000) as u16,
+        )
+    }
+    fn days_since_epoch(&self) -> i64 {
+        days_from_civil(self.year as i64, self.month, self.day)
+    }
+}
+
+/// ISO 8601, e.g. `2021-05-18T08:35:59.000Z`.
+impl fmt::Display for UtcDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.millisecond)
+    }
+}
+
+// Days between 1970-01-01 and the given date, after H. Hinnant's
+// `days_from_civil`; the year is shifted to start in March so the leap day
+// comes last.
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as u8, day as u8)
+}
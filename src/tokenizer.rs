@@ -0,0 +1,125 @@
+//! Streaming NMEA sentence tokenizer.
+//!
+//! Input arrives in chunks of any size - single bytes from an interrupt,
+//! DMA bursts, whole files. A sentence found entirely inside one chunk is
+//! returned as a slice of that chunk; only a sentence split across chunks
+//! is copied into the line buffer.
+
+use crate::error::NmeaError;
+use crate::nmea::verify_checksum;
+
+pub struct Tokenizer<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+    in_sentence: bool,
+    overlong: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Lines longer than `buffer` are rejected with `LineTooLong`.
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Tokenizer {
+            buffer,
+            len: 0,
+            in_sentence: false,
+            overlong: false,
+        }
+    }
+    /// Longest line accepted, without the line ending.
+    pub fn max_line(&self) -> usize {
+        self.buffer.len()
+    }
+    /// Inside a sentence, i.e. past a `$` and before its line ending.
+    pub fn in_sentence(&self) -> bool {
+        self.in_sentence
+    }
+    /// Drops the sentence being assembled.
+    pub fn reset(&mut self) {
+        self.in_sentence = false;
+        self.len = 0;
+        self.overlong = false;
+    }
+    /// Consumes `input` up to and including the next sentence and returns
+    /// it without its line ending, once its checksum has been verified.
+    /// `None` once `input` is used up; a partial sentence is kept for the
+    /// next call.
+    ///
+    /// Bytes outside `$...` lines are skipped. A sentence cut short by a new
+    /// `$` or broken by a non-printable byte is reported as `BadFrame`, one
+    /// that does not fit the buffer as `LineTooLong`; scanning continues at
+    /// the next `$`.
+    pub fn next<'s, 'c: 's>(&'s mut self, input: &mut &'c [u8]) -> Option<Result<&'s [u8], NmeaError>> {
+        self.next_line(input).map(|line| {
+            line.and_then(|line| {
+                if verify_checksum(line) {
+                    Ok(line)
+                } else {
+                    Err(NmeaError::ChecksumMismatch)
+                }
+            })
+        })
+    }
+    /// Like `next()`, without checking the checksum.
+    pub fn next_line<'s, 'c: 's>(&'s mut self, input: &mut &'c [u8]) -> Option<Result<&'s [u8], NmeaError>> {
+        let data: &'c [u8] = input;
+        let start = if self.in_sentence {
+            None
+        } else {
+            match data.iter().position(|c| *c == b'$') {
+                Some(i) => {
+                    self.in_sentence = true;
+                    self.len = 0;
+                    self.overlong = false;
+                    Some(i)
+                },
+                None => {
+                    *input = &[];
+                    return None;
+                },
+            }
+        };
+        let from = start.map_or(0, |i| i + 1);
+        for (j, c) in data.iter().enumerate().skip(from) {
+            match c {
+                b'\r' | b'\n' => {
+                    *input = &data[j + 1..];
+                    self.in_sentence = false;
+                    let line = match start {
+                        Some(i) => &data[i..j],
+                        None => {
+                            self.append(&data[..j]);
+                            &self.buffer[..self.len]
+                        },
+                    };
+                    if self.overlong || line.len() > self.buffer.len() {
+                        return Some(Err(NmeaError::LineTooLong));
+                    }
+                    return Some(Ok(line));
+                },
+                b'$' => {
+                    *input = &data[j..];
+                    self.in_sentence = false;
+                    return Some(Err(if self.overlong { NmeaError::LineTooLong } else { NmeaError::BadFrame }));
+                },
+                0x20..=0x7E => (),
+                _ => {
+                    *input = &data[j + 1..];
+                    self.in_sentence = false;
+                    return Some(Err(NmeaError::BadFrame));
+                },
+            }
+        }
+        let rest = &data[start.unwrap_or(0)..];
+        self.append(rest);
+        *input = &[];
+        None
+    }
+    fn append(&mut self, data: &[u8]) {
+        if self.overlong || self.len + data.len() > self.buffer.len() {
+            self.overlong = true;
+            return;
+        }
+        self.buffer[self.len..self.len + data.len()].copy_from_slice(data);
+        self.len += data.len();
+    }
+}
@@ -0,0 +1,213 @@
+use core::fmt;
+
+use crate::decimal::Decimal;
+use crate::nmea::TalkerId;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GPSTime {
+    pub hour: u8,
+    pub minute: u8,
+    /// 0-60, 60 being a leap second.
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+impl GPSTime {
+    pub fn new() -> Self {
+        GPSTime {
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millisecond: 0,
+        }
+    }
+    /// Whole seconds since midnight.
+    pub fn seconds_of_day(&self) -> u32 {
+        self.hour as u32 * 3600 + self.minute as u32 * 60 + self.second as u32
+    }
+}
+
+impl Default for GPSTime {
+    fn default() -> Self {
+        GPSTime::new()
+    }
+}
+
+impl fmt::Display for GPSTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GPSDate {
+    pub day: u8,
+    pub month: u8,
+    pub year: u8,
+}
+
+impl GPSDate {
+    pub fn new() -> Self {
+        GPSDate {
+            day: 0,
+            month: 0,
+            year: 0,
+        }
+    }
+}
+
+impl Default for GPSDate {
+    fn default() -> Self {
+        GPSDate::new()
+    }
+}
+
+impl fmt::Display for GPSDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "{:02}/{:02}/{:02}", self.day, self.month, self.year)
+    }
+}
+
+/// Latitude or longitude, kept as signed minutes of arc scaled by 1e5 - the
+/// resolution of the NEO-6 `ddmm.mmmmm` output - so no precision is lost.
+/// South and west are negative.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Coordinate {
+    minutes_e5: i32,
+}
+
+impl Coordinate {
+    pub const fn from_minutes_e5(minutes_e5: i32) -> Self {
+        Coordinate { minutes_e5 }
+    }
+    /// From signed decimal degrees scaled by 1e7, as in UBX NAV messages.
+    pub fn from_degrees_e7(degrees_e7: i32) -> Self {
+        let n = 6 * degrees_e7 as i64;
+        Coordinate { minutes_e5: ((n + 5 * n.signum()) / 10) as i32 }
+    }
+    pub fn minutes_e5(&self) -> i32 {
+        self.minutes_e5
+    }
+    pub fn is_negative(&self) -> bool {
+        self.minutes_e5 < 0
+    }
+    /// Signed decimal degrees.
+    pub fn degrees(&self) -> f64 {
+        self.minutes_e5 as f64 / 6_000_000.0
+    }
+    /// Signed decimal degrees scaled by 1e7, rounded to nearest.
+    pub fn degrees_e7(&self) -> i32 {
+        let n = 2 * 5 * self.minutes_e5 as i64;
+        ((n + 3 * n.signum()) / 6) as i32
+    }
+    /// Unsigned degrees, minutes and seconds; see `is_negative()` for the sign.
+    pub fn dms(&self) -> (u16, u8, f32) {
+        let total = self.minutes_e5.unsigned_abs();
+        let degrees = total / 6_000_000;
+        let minutes = total / 100_000 % 60;
+        let seconds = (total % 100_000) as f32 * 60.0 / 100_000.0;
+        (degrees as u16, minutes as u8, seconds)
+    }
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let e7 = self.degrees_e7();
+        let sign = if e7 < 0 { "-" } else { "" };
+        let e7 = e7.unsigned_abs();
+        write!(f, "{}{}.{:07}", sign, e7 / 10_000_000, e7 % 10_000_000)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Position {
+    pub latitude: Coordinate,
+    pub longitude: Coordinate,
+    pub altitude: Decimal,
+}
+
+impl Position {
+    pub fn new() -> Self {
+        Position {
+            latitude: Coordinate::default(),
+            longitude: Coordinate::default(),
+            altitude: Decimal::ZERO,
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::new()
+    }
+}
+
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "Lat: {}; Long: {}; Alt: {} m", self.latitude, self.longitude, self.altitude)
+    }
+}
+
+/// One satellite of the GSV sky view.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct GPSSatellite {
+    /// Constellation of the GSV sequence that reported the satellite.
+    pub talker: TalkerId,
+    pub prn: u8,
+    /// Degrees above the horizon, 0-90.
+    pub elevation: Option<u8>,
+    /// Degrees from true north, 0-359.
+    pub azimuth: Option<u16>,
+    /// C/N0 in dB-Hz, `None` while the satellite is not tracked.
+    pub snr: Option<u8>,
+    /// Listed in the GSA satellites used for the fix.
+    pub used: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FixType {
+    NoFix,
+    GPSFix,
+    DifferentialFix,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FixMode {
+    NoFix,
+    D2,
+    D3,
+}
+
+/// NMEA 2.3 positioning mode indicator (RMC, VTG, GLL).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PositioningMode {
+    Autonomous,
+    Differential,
+    Estimated,
+    Manual,
+    Simulator,
+    NotValid,
+}
+
+impl PositioningMode {
+    pub fn from_byte(c: u8) -> Option<Self> {
+        match c {
+            b'A' => Some(PositioningMode::Autonomous),
+            b'D' => Some(PositioningMode::Differential),
+            b'E' => Some(PositioningMode::Estimated),
+            b'M' => Some(PositioningMode::Manual),
+            b'S' => Some(PositioningMode::Simulator),
+            b'N' => Some(PositioningMode::NotValid),
+            _ => None,
+        }
+    }
+    /// Fix type implied by the mode; dead reckoning and manual input are not a fix.
+    pub fn fix_type(&self) -> FixType {
+        match self {
+            PositioningMode::Autonomous => FixType::GPSFix,
+            PositioningMode::Differential => FixType::DifferentialFix,
+            _ => FixType::NoFix,
+        }
+    }
+}
@@ -0,0 +1,576 @@
+//! u-blox UBX binary protocol: frame encoding, a byte-wise frame parser and
+//! the NAV/ACK messages the driver understands.
+//!
+//! Frame layout: `0xB5 0x62 class id length(u16 LE) payload ck_a ck_b`, where
+//! the 8-bit Fletcher checksum covers class, id, length and payload.
+
+use embedded_hal::serial::Write;
+use nb::block;
+
+use crate::error::UbxError;
+use crate::types::{FixType, FixMode, GPSTime};
+
+pub const SYNC_1: u8 = 0xB5;
+pub const SYNC_2: u8 = 0x62;
+/// Sync chars, class, id, length and checksum.
+pub const FRAME_OVERHEAD: usize = 8;
+/// Largest payload the parser keeps; NAV-PVT, the biggest message decoded
+/// here, has 92 bytes.
+pub const MAX_PAYLOAD: usize = 128;
+/// Longest payload taken as genuine, above that of any u-blox 6 message;
+/// a longer length field is most likely noise.
+pub const MAX_LENGTH: usize = 512;
+
+pub mod class {
+    pub const NAV: u8 = 0x01;
+    pub const RXM: u8 = 0x02;
+    pub const INF: u8 = 0x04;
+    pub const ACK: u8 = 0x05;
+    pub const CFG: u8 = 0x06;
+    pub const MON: u8 = 0x0A;
+    pub const AID: u8 = 0x0B;
+    pub const TIM: u8 = 0x0D;
+    pub const ESF: u8 = 0x10;
+
+    /// Class defined by the u-blox 6 protocol.
+    pub fn is_known(class: u8) -> bool {
+        matches!(class, NAV | RXM | INF | ACK | CFG | MON | AID | TIM | ESF)
+    }
+}
+
+pub mod nav {
+    pub const POSLLH: u8 = 0x02;
+    pub const STATUS: u8 = 0x03;
+    pub const SOL: u8 = 0x06;
+    pub const PVT: u8 = 0x07;
+    pub const TIMEUTC: u8 = 0x21;
+}
+
+pub mod ack {
+    pub const NAK: u8 = 0x00;
+    pub const ACK: u8 = 0x01;
+}
+
+/// 8-bit Fletcher checksum over class, id, length and payload.
+pub fn checksum(data: &[u8]) -> (u8, u8) {
+    let mut ck_a = 0u8;
+    let mut ck_b = 0u8;
+    for byte in data {
+        ck_a = ck_a.wrapping_add(*byte);
+        ck_b = ck_b.wrapping_add(ck_a);
+    }
+    (ck_a, ck_b)
+}
+
+/// Writes a complete frame into `out` and returns its length.
+pub fn encode_frame(class: u8, id: u8, payload: &[u8], out: &mut [u8]) -> Result<usize, UbxError> {
+    let len = payload.len() + FRAME_OVERHEAD;
+    if payload.len() > u16::MAX as usize {
+        return Err(UbxError::PayloadTooLong);
+    }
+    if out.len() < len {
+        return Err(UbxError::BufferTooSmall);
+    }
+    out[0] = SYNC_1;
+    out[1] = SYNC_2;
+    out[2] = class;
+    out[3] = id;
+    out[4..6].copy_from_slice(&(payload.len() as u16).to_le_bytes());
+    out[6..len - 2].copy_from_slice(payload);
+    let (ck_a, ck_b) = checksum(&out[2..len - 2]);
+    out[len - 2] = ck_a;
+    out[len - 1] = ck_b;
+    Ok(len)
+}
+
+/// Sends a frame byte by byte, without an intermediate buffer.
+pub fn write_frame<Tx: Write<u8>>(tx: &mut Tx, class: u8, id: u8, payload: &[u8]) -> Result<(), Tx::Error> {
+    let len = (payload.len() as u16).to_le_bytes();
+    let header = [class, id, len[0], len[1]];
+    let (mut ck_a, mut ck_b) = (0u8, 0u8);
+    block!(tx.write(SYNC_1))?;
+    block!(tx.write(SYNC_2))?;
+    for byte in header.iter().chain(payload.iter()) {
+        ck_a = ck_a.wrapping_add(*byte);
+        ck_b = ck_b.wrapping_add(ck_a);
+        block!(tx.write(*byte))?;
+    }
+    block!(tx.write(ck_a))?;
+    block!(tx.write(ck_b))?;
+    Ok(())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum State {
+    Sync1,
+    Sync2,
+    Class,
+    Id,
+    Length1,
+    Length2,
+    Payload,
+    ChecksumA,
+    ChecksumB,
+}
+
+/// Byte-wise UBX frame parser holding the last complete frame until it is
+/// taken with `frame()` / `clear()`.
+///
+/// Frames that are not kept, because of a payload over `MAX_PAYLOAD` or a
+/// frame still waiting, are skipped by their announced length, so that no
+/// binary payload is mistaken for NMEA. A header with an unknown class or
+/// a length over `MAX_LENGTH` is taken as a false sync instead, and the
+/// parser looks for the next frame straight away.
+pub struct UbxParser {
+    state: State,
+    // class, id and length of the frame being received
+    header: [u8; 4],
+    remaining: usize,
+    // error reported once a skipped frame has passed
+    skip: Option<UbxError>,
+    sum: (u8, u8),
+    ck_a: u8,
+    // complete frame
+    class: u8,
+    id: u8,
+    len: usize,
+    payload: [u8; MAX_PAYLOAD],
+    ready: bool,
+}
+
+impl UbxParser {
+    pub fn new() -> Self {
+        UbxParser {
+            state: State::Sync1,
+            header: [0; 4],
+            remaining: 0,
+            skip: None,
+            sum: (0, 0),
+            ck_a: 0,
+            class: 0,
+            id: 0,
+            len: 0,
+            payload: [0; MAX_PAYLOAD],
+            ready: false,
+        }
+    }
+    /// Inside a frame, i.e. past the first sync char.
+    pub fn in_frame(&self) -> bool {
+        self.state != State::Sync1
+    }
+    /// Complete frame waiting to be taken.
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+    /// Feeds one byte. Returns `Ok(true)` when a frame has just completed.
+    /// While a frame is waiting to be taken the next one is dropped with
+    /// `Overflow`.
+    ///
+    /// `FalseSync` means the bytes since the first sync char were no frame
+    /// header; `byte` is not taken, pass it on to the NMEA parser.
+    pub fn push(&mut self, byte: u8) -> Result<bool, UbxError> {
+        match self.state {
+            State::Sync1 => {
+                if byte == SYNC_1 {
+                    self.state = State::Sync2;
+                }
+            },
+            State::Sync2 => {
+                if byte != SYNC_2 {
+                    self.state = State::Sync1;
+                    return Err(UbxError::FalseSync);
+                }
+                self.state = State::Class;
+            },
+            State::Class => {
+                self.header[0] = byte;
+                self.state = State::Id;
+            },
+            State::Id => {
+                self.header[1] = byte;
+                self.state = State::Length1;
+            },
+            State::Length1 => {
+                self.header[2] = byte;
+                self.state = State::Length2;
+            },
+            State::Length2 => {
+                self.header[3] = byte;
+                self.remaining = u16::from_le_bytes([self.header[2], self.header[3]]) as usize;
+                if !class::is_known(self.header[0]) || self.remaining > MAX_LENGTH {
+                    self.state = State::Sync1;
+                    return Err(UbxError::FalseSync);
+                }
+                self.sum = checksum(&self.header);
+                self.skip = if self.remaining > MAX_PAYLOAD {
+                    Some(UbxError::PayloadTooLong)
+                } else if self.ready {
+                    Some(UbxError::Overflow)
+                } else {
+                    None
+                };
+                if self.skip.is_none() {
+                    self.class = self.header[0];
+                    self.id = self.header[1];
+                    self.len = self.remaining;
+                }
+                self.state = if self.remaining == 0 { State::ChecksumA } else { State::Payload };
+            },
+            State::Payload => {
+                if self.skip.is_none() {
+                    self.payload[self.len - self.remaining] = byte;
+                }
+                self.sum.0 = self.sum.0.wrapping_add(byte);
+                self.sum.1 = self.sum.1.wrapping_add(self.sum.0);
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    self.state = State::ChecksumA;
+                }
+            },
+            State::ChecksumA => {
+                self.ck_a = byte;
+                self.state = State::ChecksumB;
+            },
+            State::ChecksumB => {
+                self.state = State::Sync1;
+                if self.sum != (self.ck_a, byte) {
+                    return Err(UbxError::ChecksumMismatch);
+                }
+                if let Some(error) = self.skip {
+                    return Err(error);
+                }
+                self.ready = true;
+                return Ok(true);
+            },
+        }
+        Ok(false)
+    }
+    /// The complete frame as `(class, id, payload)`.
+    pub fn frame(&self) -> Option<(u8, u8, &[u8])> {
+        if self.ready {
+            Some((self.class, self.id, &self.payload[..self.len]))
+        } else {
+            None
+        }
+    }
+    pub fn clear(&mut self) {
+        self.ready = false;
+    }
+    /// Drops the complete frame and the one being received.
+    pub fn reset(&mut self) {
+        self.state = State::Sync1;
+        self.ready = false;
+    }
+}
+
+impl Default for UbxParser {
+    fn default() -> Self {
+        UbxParser::new()
+    }
+}
+
+/// `gpsFix` field of NAV-STATUS, NAV-SOL and NAV-PVT.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GpsFix {
+    NoFix,
+    DeadReckoning,
+    Fix2D,
+    Fix3D,
+    GpsDeadReckoning,
+    TimeOnly,
+}
+
+impl GpsFix {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => GpsFix::DeadReckoning,
+            2 => GpsFix::Fix2D,
+            3 => GpsFix::Fix3D,
+            4 => GpsFix::GpsDeadReckoning,
+            5 => GpsFix::TimeOnly,
+            _ => GpsFix::NoFix,
+        }
+    }
+    /// 2D/3D mode of the fix; dead reckoning alone and time-only are no fix.
+    pub fn fix_mode(&self) -> FixMode {
+        match self {
+            GpsFix::Fix2D => FixMode::D2,
+            GpsFix::Fix3D | GpsFix::GpsDeadReckoning => FixMode::D3,
+            _ => FixMode::NoFix,
+        }
+    }
+    /// Fix type given the `gpsFixOk` (bit 0) and `diffSoln` (bit 1) flags.
+    pub fn fix_type(&self, flags: u8) -> FixType {
+        if flags & 0x01 == 0 || self.fix_mode() == FixMode::NoFix {
+            FixType::NoFix
+        } else if flags & 0x02 != 0 {
+            FixType::DifferentialFix
+        } else {
+            FixType::GPSFix
+        }
+    }
+}
+
+/// NAV-POSLLH, geodetic position.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NavPosllh {
+    /// GPS time of week, ms
+    pub itow: u32,
+    /// Degrees * 1e7
+    pub lon: i32,
+    /// Degrees * 1e7
+    pub lat: i32,
+    /// Above ellipsoid, mm
+    pub height: i32,
+    /// Above mean sea level, mm
+    pub height_msl: i32,
+    /// Horizontal accuracy estimate, mm
+    pub h_acc: u32,
+    /// Vertical accuracy estimate, mm
+    pub v_acc: u32,
+}
+
+/// NAV-STATUS, receiver navigation status.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NavStatus {
+    pub itow: u32,
+    pub fix: GpsFix,
+    /// bit 0 gpsFixOk, bit 1 diffSoln, bit 2 wknSet, bit 3 towSet
+    pub flags: u8,
+    /// Time to first fix, ms
+    pub ttff: u32,
+    /// Time since startup, ms
+    pub msss: u32,
+}
+
+/// NAV-SOL, navigation solution in ECEF.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NavSol {
+    pub itow: u32,
+    /// Fractional time of week, ns
+    pub ftow: i32,
+    pub week: i16,
+    pub fix: GpsFix,
+    /// bit 0 gpsFixOk, bit 1 diffSoln, bit 2 wknSet, bit 3 towSet
+    pub flags: u8,
+    /// ECEF position, cm
+    pub ecef: [i32; 3],
+    /// 3D position accuracy, cm
+    pub p_acc: u32,
+    /// ECEF velocity, cm/s
+    pub ecef_vel: [i32; 3],
+    /// Speed accuracy, cm/s
+    pub s_acc: u32,
+    /// Position DOP * 100
+    pub pdop: u16,
+    pub num_sv: u8,
+}
+
+/// NAV-TIMEUTC, UTC time solution.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NavTimeUtc {
+    pub itow: u32,
+    /// Time accuracy, ns
+    pub t_acc: u32,
+    /// Fraction of second, ns, may be negative
+    pub nano: i32,
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+    /// bit 0 validTOW, bit 1 validWKN, bit 2 validUTC
+    pub valid: u8,
+}
+
+/// NAV-PVT, position, velocity and time in one message (protocol 14+, not
+/// sent by the NEO-6 itself).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NavPvt {
+    pub itow: u32,
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+    /// bit 0 validDate, bit 1 validTime
+    pub valid: u8,
+    pub t_acc: u32,
+    pub nano: i32,
+    pub fix: GpsFix,
+    /// bit 0 gnssFixOK, bit 1 diffSoln
+    pub flags: u8,
+    pub num_sv: u8,
+    /// Degrees * 1e7
+    pub lon: i32,
+    /// Degrees * 1e7
+    pub lat: i32,
+    /// mm
+    pub height: i32,
+    /// mm
+    pub height_msl: i32,
+    pub h_acc: u32,
+    pub v_acc: u32,
+    /// NED velocity, mm/s
+    pub vel_ned: [i32; 3],
+    /// Ground speed, mm/s
+    pub ground_speed: i32,
+    /// Heading of motion, degrees * 1e5
+    pub heading: i32,
+    pub s_acc: u32,
+    pub head_acc: u32,
+    /// Position DOP * 100
+    pub pdop: u16,
+}
+
+/// A decoded UBX message.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UbxMessage {
+    NavPosllh(NavPosllh),
+    NavStatus(NavStatus),
+    NavSol(NavSol),
+    NavTimeUtc(NavTimeUtc),
+    NavPvt(NavPvt),
+    /// Class and id of the acknowledged message
+    AckAck(u8, u8),
+    /// Class and id of the rejected message
+    AckNak(u8, u8),
+}
+
+impl UbxMessage {
+    /// UTC time of the solution, if the message carries a valid one.
+    pub fn time(&self) -> Option<GPSTime> {
+        match self {
+            UbxMessage::NavTimeUtc(data) if data.valid & 0x04 != 0 => {
+                Some(GPSTime { hour: data.hour, minute: data.min, second: data.sec, millisecond: millis(data.nano) })
+            },
+            UbxMessage::NavPvt(data) if data.valid & 0x03 == 0x03 => {
+                Some(GPSTime { hour: data.hour, minute: data.min, second: data.sec, millisecond: millis(data.nano) })
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Milliseconds of the `nano` fraction. A negative fraction, meaning the
+/// time is just before the given second, is taken as 0.
+pub fn millis(nano: i32) -> u16 {
+    (nano.max(0) / 1_000_000).min(999) as u16
+}
+
+fn u16_le(p: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([p[at], p[at + 1]])
+}
+fn u32_le(p: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([p[at], p[at + 1], p[at + 2], p[at + 3]])
+}
+fn i32_le(p: &[u8], at: usize) -> i32 {
+    u32_le(p, at) as i32
+}
+
+fn require_len(payload: &[u8], len: usize) -> Result<(), UbxError> {
+    if payload.len() != len {
+        return Err(UbxError::BadLength);
+    }
+    Ok(())
+}
+
+/// Decodes the payload of a NAV or ACK message.
+pub fn parse_message(class: u8, id: u8, p: &[u8]) -> Result<UbxMessage, UbxError> {
+    match (class, id) {
+        (class::NAV, nav::POSLLH) => {
+            require_len(p, 28)?;
+            Ok(UbxMessage::NavPosllh(NavPosllh {
+                itow: u32_le(p, 0),
+                lon: i32_le(p, 4),
+                lat: i32_le(p, 8),
+                height: i32_le(p, 12),
+                height_msl: i32_le(p, 16),
+                h_acc: u32_le(p, 20),
+                v_acc: u32_le(p, 24),
+            }))
+        },
+        (class::NAV, nav::STATUS) => {
+            require_len(p, 16)?;
+            Ok(UbxMessage::NavStatus(NavStatus {
+                itow: u32_le(p, 0),
+                fix: GpsFix::from_u8(p[4]),
+                flags: p[5],
+                ttff: u32_le(p, 8),
+                msss: u32_le(p, 12),
+            }))
+        },
+        (class::NAV, nav::SOL) => {
+            require_len(p, 52)?;
+            Ok(UbxMessage::NavSol(NavSol {
+                itow: u32_le(p, 0),
+                ftow: i32_le(p, 4),
+                week: u16_le(p, 8) as i16,
+                fix: GpsFix::from_u8(p[10]),
+                flags: p[11],
+                ecef: [i32_le(p, 12), i32_le(p, 16), i32_le(p, 20)],
+                p_acc: u32_le(p, 24),
+                ecef_vel: [i32_le(p, 28), i32_le(p, 32), i32_le(p, 36)],
+                s_acc: u32_le(p, 40),
+                pdop: u16_le(p, 44),
+                num_sv: p[47],
+            }))
+        },
+        (class::NAV, nav::TIMEUTC) => {
+            require_len(p, 20)?;
+            Ok(UbxMessage::NavTimeUtc(NavTimeUtc {
+                itow: u32_le(p, 0),
+                t_acc: u32_le(p, 4),
+                nano: i32_le(p, 8),
+                year: u16_le(p, 12),
+                month: p[14],
+                day: p[15],
+                hour: p[16],
+                min: p[17],
+                sec: p[18],
+                valid: p[19],
+            }))
+        },
+        (class::NAV, nav::PVT) => {
+            require_len(p, 92)?;
+            Ok(UbxMessage::NavPvt(NavPvt {
+                itow: u32_le(p, 0),
+                year: u16_le(p, 4),
+                month: p[6],
+                day: p[7],
+                hour: p[8],
+                min: p[9],
+                sec: p[10],
+                valid: p[11],
+                t_acc: u32_le(p, 12),
+                nano: i32_le(p, 16),
+                fix: GpsFix::from_u8(p[20]),
+                flags: p[21],
+                num_sv: p[23],
+                lon: i32_le(p, 24),
+                lat: i32_le(p, 28),
+                height: i32_le(p, 32),
+                height_msl: i32_le(p, 36),
+                h_acc: u32_le(p, 40),
+                v_acc: u32_le(p, 44),
+                vel_ned: [i32_le(p, 48), i32_le(p, 52), i32_le(p, 56)],
+                ground_speed: i32_le(p, 60),
+                heading: i32_le(p, 64),
+                s_acc: u32_le(p, 68),
+                head_acc: u32_le(p, 72),
+                pdop: u16_le(p, 76),
+            }))
+        },
+        (class::ACK, ack::ACK) => {
+            require_len(p, 2)?;
+            Ok(UbxMessage::AckAck(p[0], p[1]))
+        },
+        (class::ACK, ack::NAK) => {
+            require_len(p, 2)?;
+            Ok(UbxMessage::AckNak(p[0], p[1]))
+        },
+        _ => Err(UbxError::UnknownMessage),
+    }
+}
@@ -0,0 +1,126 @@
+//! Receiver liveness monitor.
+//!
+//! Driven by a monotonic tick count from the application, e.g. milliseconds
+//! kept by a timer. The counts wrap, so only intervals shorter than half
+//! the `u32` range are meaningful. Activity is sampled on every `update()`,
+//! so the timestamps have the resolution of the calling loop.
+
+use crate::cfg::StartMode;
+
+/// Default for `Watchdog::set_timeout`, in ticks: 2 s at 1 kHz.
+pub const DEFAULT_TIMEOUT: u32 = 2000;
+
+/// State of the receiver as seen from the MCU.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Liveness {
+    /// No valid sentence or UBX frame received yet.
+    NotDetected,
+    /// The receiver was detected, but has sent nothing valid within the
+    /// timeout.
+    Silent,
+    /// Valid data, without a fresh fix.
+    Streaming,
+    /// Valid data with a fresh fix.
+    Fix,
+}
+
+/// What to do when the receiver stays silent or undetected.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Recovery {
+    /// Restart the receiver with CFG-RST, see `NEO6::reset_receiver`.
+    Reset(StartMode),
+    /// Find the baud rate again, see `NEO6::negotiate_baud_rate`.
+    Reprobe,
+}
+
+pub struct Watchdog {
+    timeout: u32,
+    // action and the ticks without valid data before it is due
+    recovery: Option<(Recovery, u32)>,
+    bytes: u32,
+    received: u32,
+    last_byte: Option<u32>,
+    last_valid: Option<u32>,
+    // last tick the receiver was alive, or the action was last taken
+    since: Option<u32>,
+    state: Liveness,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Watchdog {
+            timeout: DEFAULT_TIMEOUT,
+            recovery: None,
+            bytes: 0,
+            received: 0,
+            last_byte: None,
+            last_valid: None,
+            since: None,
+            state: Liveness::NotDetected,
+        }
+    }
+    /// Ticks without valid data before the receiver is considered silent.
+    pub fn set_timeout(&mut self, ticks: u32) {
+        self.timeout = ticks;
+    }
+    /// Asks for `action` once the receiver has been silent or undetected for
+    /// `after` ticks, and again every `after` ticks while that lasts.
+    pub fn set_recovery(&mut self, action: Recovery, after: u32) {
+        self.recovery = Some((action, after));
+    }
+    pub fn clear_recovery(&mut self) {
+        self.recovery = None;
+    }
+    /// Samples the running totals of received bytes and of valid sentences
+    /// and frames at `now`. Returns the recovery action if it is due.
+    pub fn update(&mut self, now: u32, bytes: u32, received: u32, fix: bool) -> Option<Recovery> {
+        if bytes != self.bytes {
+            self.bytes = bytes;
+            self.last_byte = Some(now);
+        }
+        if received != self.received {
+            self.received = received;
+            self.last_valid = Some(now);
+        }
+        let alive = match self.last_valid {
+            Some(at) => now.wrapping_sub(at) <= self.timeout,
+            None => false,
+        };
+        self.state = match self.last_valid {
+            None => Liveness::NotDetected,
+            Some(_) if !alive => Liveness::Silent,
+            Some(_) if fix => Liveness::Fix,
+            Some(_) => Liveness::Streaming,
+        };
+        let since = *self.since.get_or_insert(now);
+        if alive {
+            self.since = Some(now);
+            return None;
+        }
+        match self.recovery {
+            Some((action, after)) if now.wrapping_sub(since) >= after => {
+                self.since = Some(now);
+                Some(action)
+            },
+            _ => None,
+        }
+    }
+    /// State as of the last `update()`.
+    pub fn state(&self) -> Liveness {
+        self.state
+    }
+    /// Tick the last byte of any kind was seen at.
+    pub fn last_byte(&self) -> Option<u32> {
+        self.last_byte
+    }
+    /// Tick the last valid sentence or UBX frame was seen at.
+    pub fn last_valid(&self) -> Option<u32> {
+        self.last_valid
+    }
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Watchdog::new()
+    }
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use common::*;
+use neo6::cfg;
+use neo6::{New, NEO6, BaudError, LineError};
+
+/// Serial line between the MCU and a simulated module sending `FIX_RMC`
+/// in a loop. Bytes only arrive intact when both ends use the same rate.
+struct Link {
+    mcu: u32,
+    module: u32,
+    /// Module applies CFG-PRT.
+    obeys: bool,
+    /// Reads the module stays silent for after switching.
+    startup: u32,
+    silent: u32,
+    pos: usize,
+    sent: Vec<u8>,
+}
+
+struct LinkRx(Rc<RefCell<Link>>);
+struct LinkTx(Rc<RefCell<Link>>);
+
+impl embedded_hal::serial::Read<u8> for LinkRx {
+    type Error = LineError;
+    fn read(&mut self) -> nb::Result<u8, LineError> {
+        let mut link = self.0.borrow_mut();
+        link.pos = (link.pos + 1) % FIX_RMC.len();
+        if link.silent > 0 {
+            link.silent -= 1;
+            return Err(nb::Error::WouldBlock);
+        }
+        if link.mcu == link.module {
+            Ok(FIX_RMC[link.pos])
+        } else {
+            Err(nb::Error::Other(LineError::Framing))
+        }
+    }
+}
+
+impl embedded_hal::serial::Write<u8> for LinkTx {
+    type Error = ();
+    fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+        self.0.borrow_mut().sent.push(word);
+        Ok(())
+    }
+    fn flush(&mut self) -> nb::Result<(), ()> {
+        let mut link = self.0.borrow_mut();
+        // CFG-PRT: class 0x06, id 0x00, 20 bytes, baud rate at payload offset 8
+        if link.obeys && link.sent.len() >= 28 && link.sent[link.sent.len() - 26..][..2] == [0x06, 0x00] {
+            let at = link.sent.len() - 22 + 8;
+            let baud = u32::from_le_bytes([link.sent[at], link.sent[at + 1], link.sent[at + 2], link.sent[at + 3]]);
+            link.module = baud;
+            link.silent = link.startup;
+        }
+        link.sent.clear();
+        Ok(())
+    }
+}
+
+type Outcome = (Result<u32, BaudError<()>>, Rc<RefCell<Link>>, Vec<u32>);
+
+fn negotiate(module: u32, obeys: bool, target: u32) -> Outcome {
+    negotiate_with_startup(module, obeys, 0, target)
+}
+
+fn negotiate_with_startup(module: u32, obeys: bool, startup: u32, target: u32) -> Outcome {
+    let link = Rc::new(RefCell::new(Link { mcu: 0, module, obeys, startup, silent: 0, pos: 0, sent: Vec::new() }));
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, LinkRx(link.clone()), LinkTx(link.clone()));
+    let mut timer = MockTimer::new();
+    let mut tried = Vec::new();
+    let mcu = link.clone();
+    let result = neo.negotiate_baud_rate(target, |rate| {
+        tried.push(rate);
+        mcu.borrow_mut().mcu = rate;
+    }, &mut timer, 300);
+    (result, link, tried)
+}
+
+#[test]
+fn probes_and_switches_to_target() {
+    let (result, link, tried) = negotiate(38_400, true, 115_200);
+    assert_eq!(result, Ok(115_200));
+    assert_eq!(link.borrow().module, 115_200);
+    assert_eq!(link.borrow().mcu, 115_200);
+    assert_eq!(tried, [9600, 115_200, 38_400, 115_200]);
+}
+
+#[test]
+fn already_at_target() {
+    let (result, _, tried) = negotiate(115_200, true, 115_200);
+    assert_eq!(result, Ok(115_200));
+    assert_eq!(tried, [9600, 115_200]);
+}
+
+#[test]
+fn falls_back_when_switch_fails() {
+    let (result, link, _) = negotiate(cfg::DEFAULT_BAUD_RATE, false, 115_200);
+    assert_eq!(result, Ok(cfg::DEFAULT_BAUD_RATE));
+    assert_eq!(link.borrow().mcu, cfg::DEFAULT_BAUD_RATE);
+}
+
+#[test]
+fn module_ignoring_the_switch_is_found_again() {
+    let (result, link, tried) = negotiate(38_400, false, 115_200);
+    assert_eq!(result, Ok(38_400));
+    assert_eq!(link.borrow().module, 38_400);
+    assert_eq!(link.borrow().mcu, 38_400);
+    // back to the previous rate, then the fallback command, which it
+    // ignores as well
+    assert_eq!(tried, [9600, 115_200, 38_400, 115_200, 38_400, 9600, 38_400]);
+}
+
+#[test]
+fn slow_module_is_found_at_target() {
+    // silent for longer than the first wait after the switch
+    let (result, link, tried) = negotiate_with_startup(38_400, true, 500, 115_200);
+    assert_eq!(result, Ok(115_200));
+    assert_eq!(link.borrow().module, 115_200);
+    assert_eq!(link.borrow().mcu, 115_200);
+    assert_eq!(tried, [9600, 115_200, 38_400, 115_200, 38_400, 115_200]);
+}
+
+#[test]
+fn silent_module_leaves_default_rate() {
+    let (result, link, _) = negotiate(0, true, 115_200);
+    assert_eq!(result, Err(BaudError::NotDetected));
+    assert_eq!(link.borrow().mcu, cfg::DEFAULT_BAUD_RATE);
+}
@@ -0,0 +1,253 @@
+mod common;
+
+use common::*;
+use neo6::{TalkerId, Sentence, parse_sentence, parse_vtg, parse_gll, parse_zda, GsvAssembler, parse_gsv, PositioningMode, Decimal, Coordinate, parse_coordinate, NmeaError, GPS_Data, GPS_Statement, FixType, FixMode, GPSTime, GPSDate, parse_rmc, parse_gsa, parse_gga, verify_checksum, split_sentence, Tokenizer};
+
+fn framed(sentence: &[u8], f: impl FnOnce(GPS_Statement, &[u8])) {
+    let mut buf = [0u8; 100];
+    let mut tokenizer = Tokenizer::new(&mut buf);
+    let mut input = sentence;
+    let line = tokenizer.next_line(&mut input).unwrap().unwrap();
+    let (talker, cmd, info) = split_sentence(line).unwrap();
+    assert_eq!(talker, TalkerId::GP);
+    f(cmd, info);
+}
+
+#[test]
+fn frames_sentence_tags() {
+    framed(FIX_RMC, |cmd, _| assert_eq!(cmd, GPS_Statement::RMC));
+    framed(FIX_GGA, |cmd, _| assert_eq!(cmd, GPS_Statement::GGA));
+    framed(FIX_GSA, |cmd, _| assert_eq!(cmd, GPS_Statement::GSA));
+}
+
+#[test]
+fn rmc_with_fix() {
+    framed(FIX_RMC, |_, info| {
+        let rmc = parse_rmc(info).unwrap();
+        assert!(rmc.valid);
+        assert_eq!(rmc.time, GPSTime { hour: 8, minute: 35, second: 59, millisecond: 0 });
+        assert_eq!(rmc.mode, Some(PositioningMode::Autonomous));
+        assert_eq!(rmc.magnetic_variation, None);
+        assert_eq!(rmc.latitude, Coordinate::from_minutes_e5(300_647_052));
+        assert_eq!(rmc.longitude, Coordinate::from_minutes_e5(119_418_623));
+        assert_eq!(rmc.date, GPSDate { day: 18, month: 5, year: 21 });
+        assert_eq!(rmc.speed.to_string(), "0.148");
+    });
+}
+
+#[test]
+fn gga_with_fix() {
+    framed(FIX_GGA, |_, info| {
+        let gga = parse_gga(info).unwrap();
+        assert_eq!(gga.time, GPSTime { hour: 8, minute: 35, second: 59, millisecond: 0 });
+        assert_eq!(gga.fix, FixType::GPSFix);
+        assert_eq!(gga.satellites_used, 8);
+        assert_eq!(gga.position.altitude.to_string(), "229.4");
+        assert_eq!(gga.position.latitude.degrees_e7(), 501_078_420);
+        assert_eq!(gga.position.longitude.degrees_e7(), 199_031_038);
+    });
+}
+
+#[test]
+fn gsa_with_fix() {
+    framed(FIX_GSA, |_, info| {
+        let gsa = parse_gsa(info).unwrap();
+        assert_eq!(gsa.fix, FixMode::D3);
+        assert_eq!(gsa.satellite_ids[..8], [10, 32, 22, 27, 14, 18, 1, 8]);
+        assert_eq!(gsa.pdop.to_string(), "1.86");
+        assert_eq!(gsa.hdop.to_string(), "1.01");
+        assert_eq!(gsa.vdop.to_string(), "1.56");
+    });
+}
+
+#[test]
+fn cold_start_has_no_fix() {
+    let mut data = GPS_Data::new();
+    for sentence in sentences(COLD_START) {
+        framed(sentence, |cmd, info| match cmd {
+            GPS_Statement::RMC => data.update_rmc(parse_rmc(info).unwrap()),
+            GPS_Statement::GGA => data.update_gga(parse_gga(info).unwrap()),
+            GPS_Statement::GSA => data.update_gsa(parse_gsa(info).unwrap()),
+            GPS_Statement::GLL => data.update_gll(parse_gll(info).unwrap()),
+            _ => (),
+        });
+    }
+    assert!(!data.is_valid());
+    assert_eq!(data.get_fix_type(), FixType::NoFix);
+    assert_eq!(data.get_fix_mode(), FixMode::NoFix);
+    assert_eq!(data.satellites_no(), 0);
+}
+
+#[test]
+fn captures_have_valid_checksums() {
+    for sentence in sentences(FIX_EPOCH).chain(sentences(COLD_START)) {
+        assert!(verify_checksum(sentence), "{:?}", core::str::from_utf8(sentence));
+    }
+}
+
+#[test]
+fn corrupt_sentences_fail_checksum() {
+    // latitude 5006 -> 5007, single flipped bit
+    assert!(!verify_checksum(b"$GPGGA,083559.00,5007.47052,N,01954.18623,E,1,08,1.01,229.4,M,40.1,M,,*5E\r\n"));
+    assert!(!verify_checksum(b"$GPRMC,,V,,,,,,,,,,N\r\n"));
+    assert!(!verify_checksum(b"$GPRMC,,V,,,,,,,,,,N*5\r\n"));
+    assert!(!verify_checksum(b"$GPRMC,,V,,,,,,,,,,N*G3\r\n"));
+    assert!(verify_checksum(b"$GPRMC,,V,,,,,,,,,,N*53"));
+}
+
+#[test]
+fn truncated_and_garbled_fields_are_errors() {
+    assert_eq!(parse_gga(b"083559.00,5006.47052,N").unwrap_err(), NmeaError::MissingField);
+    assert_eq!(parse_gga(b"0835,5006.47052,N,01954.18623,E,1,08,1.01,229.4,M,40.1,M,,").unwrap_err(), NmeaError::FieldTooShort);
+    assert_eq!(parse_gga(b"083559.00,50,N,01954.18623,E,1,08,1.01,229.4,M,40.1,M,,").unwrap_err(), NmeaError::FieldTooShort);
+    assert_eq!(parse_gga(b"083559.00,5006.47052,N,01954.18623,E,1,0x,1.01,229.4,M,40.1,M,,").unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(parse_rmc(b"083559.00,A,5006.47052,N,01954.18623,E,0.148,,1805,,,A").unwrap_err(), NmeaError::FieldTooShort);
+    assert_eq!(parse_rmc(b"083559.00,A,5006.47052,N,01954.18623,E,1.4.8,,180521,,,A").unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(parse_gsa(b"A,3,10,32,22,27").unwrap_err(), NmeaError::MissingField);
+}
+
+#[test]
+fn counts_over_255_are_rejected() {
+    assert_eq!(parse_gga(b"083559.00,5006.47052,N,01954.18623,E,1,264,1.01,229.4,M,40.1,M,,").unwrap_err(), NmeaError::InvalidValue);
+    assert_eq!(parse_gsa(b"A,3,10,32,266,27,,,,,,,,,2.05,1.01,1.79").unwrap_err(), NmeaError::InvalidValue);
+    assert_eq!(parse_gsa(b"A,3,10,32,255,27,,,,,,,,,2.05,1.01,1.79").unwrap().satellite_ids[2], 255);
+}
+
+#[test]
+fn framing_errors() {
+    let mut buf = [0u8; 100];
+    let mut tokenizer = Tokenizer::new(&mut buf);
+    let mut input: &[u8] = b"";
+    assert!(tokenizer.next_line(&mut input).is_none());
+    let mut input: &[u8] = b"$GPRMC,0835$GPTXT*12\r\n";
+    assert_eq!(tokenizer.next_line(&mut input).unwrap().unwrap_err(), NmeaError::BadFrame);
+    let line = tokenizer.next_line(&mut input).unwrap().unwrap();
+    assert_eq!(split_sentence(line).unwrap_err(), NmeaError::ChecksumMismatch);
+}
+
+#[test]
+fn coordinates_in_decimal_degrees() {
+    let lat = parse_coordinate(b"5006.47052", b"N", 90).unwrap();
+    assert_eq!(lat.to_string(), "50.1078420");
+    assert!((lat.degrees() - 50.107842).abs() < 1e-9);
+    let (d, m, s) = lat.dms();
+    assert_eq!((d, m), (50, 6));
+    assert!((s - 28.2312).abs() < 1e-3);
+
+    let lon = parse_coordinate(b"07400.00060", b"W", 180).unwrap();
+    assert_eq!(lon.to_string(), "-74.0000100");
+    assert!(lon.is_negative());
+
+    let lat = parse_coordinate(b"0030.0", b"S", 90).unwrap();
+    assert_eq!(lat.degrees_e7(), -5_000_000);
+    assert_eq!(lat.to_string(), "-0.5000000");
+
+    assert_eq!(parse_coordinate(b"5060.0", b"N", 90).unwrap_err(), NmeaError::InvalidValue);
+    assert_eq!(parse_coordinate(b"9100.0", b"N", 90).unwrap_err(), NmeaError::InvalidValue);
+    assert_eq!(parse_coordinate(b"5006.4", b"X", 90).unwrap_err(), NmeaError::InvalidValue);
+    assert_eq!(parse_coordinate(b"5006.4", b"", 90).unwrap_err(), NmeaError::MissingField);
+    assert_eq!(parse_coordinate(b"5006.4a", b"N", 90).unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(parse_coordinate(b"06.4", b"N", 90).unwrap_err(), NmeaError::FieldTooShort);
+}
+
+#[test]
+fn altitude_below_geoid() {
+    let gga = parse_gga(b"083559.00,5006.47052,N,01954.18623,E,1,08,1.01,-12.05,M,40.1,M,,").unwrap();
+    assert_eq!(gga.position.altitude.to_string(), "-12.05");
+}
+
+#[test]
+fn rmc_magnetic_variation() {
+    let rmc = parse_rmc(b"123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W").unwrap();
+    assert_eq!(rmc.time, GPSTime { hour: 12, minute: 35, second: 19, millisecond: 0 });
+    assert_eq!(rmc.magnetic_variation, Some(Decimal::new(-31, 1)));
+    assert_eq!(rmc.mode, None);
+    assert_eq!(rmc.latitude.to_string(), "48.1173000");
+
+    assert_eq!(parse_rmc(b"123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,").unwrap_err(), NmeaError::MissingField);
+    assert_eq!(parse_rmc(b"123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,,,X").unwrap_err(), NmeaError::InvalidValue);
+    assert_eq!(parse_rmc(b"126519,A,4807.038,N,01131.000,E,022.4,084.4,230394,,,A").unwrap_err(), NmeaError::InvalidValue);
+}
+
+#[test]
+fn gsv_parts() {
+    let gsv = parse_gsv(b"3,3,11,27,25,051,29,32,31,089,27,36,24,161,").unwrap();
+    assert_eq!((gsv.total, gsv.number, gsv.in_view), (3, 3, 11));
+    assert_eq!(gsv.satellites().len(), 3);
+    assert_eq!(gsv.satellites()[2].prn, 36);
+    assert_eq!(gsv.satellites()[2].snr, None);
+    assert_eq!(gsv.satellites()[0].azimuth, Some(51));
+
+    assert_eq!(parse_gsv(b"3,1,11,01,46").unwrap_err(), NmeaError::MissingField);
+    assert_eq!(parse_gsv(b"3,4,11").unwrap_err(), NmeaError::InvalidValue);
+    assert_eq!(parse_gsv(b"1,1,01,01,91,290,28").unwrap_err(), NmeaError::InvalidValue);
+}
+
+#[test]
+fn gsv_sequence_assembly() {
+    let mut assembler = GsvAssembler::new();
+    let part = |s: &[u8]| parse_gsv(s).unwrap();
+    assert!(assembler.add(TalkerId::GP, &part(b"3,1,11,01,46,290,28,03,07,020,,08,60,180,33,10,38,067,30")).unwrap().is_none());
+    // part 3 without part 2
+    assert_eq!(assembler.add(TalkerId::GP, &part(b"3,3,11,27,25,051,29,32,31,089,27,36,24,161,")).unwrap_err(), NmeaError::BadSequence);
+    assert_eq!(assembler.add(TalkerId::GP, &part(b"3,2,11,14,20,138,24,18,33,308,31,21,05,210,,22,48,121,35")).unwrap_err(), NmeaError::BadSequence);
+
+    let view = assembler.add(TalkerId::GP, &part(b"1,1,02,01,46,290,28,03,07,020,")).unwrap().unwrap();
+    assert_eq!(view.len(), 2);
+    assert_eq!(view.tracked(), 1);
+}
+
+#[test]
+fn vtg_course_and_speed() {
+    let vtg = parse_vtg(b",T,,M,0.148,N,0.274,K,A").unwrap();
+    assert_eq!(vtg.speed_knots.to_string(), "0.148");
+    assert_eq!(vtg.speed_kmh.to_string(), "0.274");
+    assert_eq!(vtg.course_magnetic, None);
+    assert_eq!(vtg.mode, Some(PositioningMode::Autonomous));
+
+    let vtg = parse_vtg(b"054.7,T,034.4,M,005.5,N,010.2,K").unwrap();
+    assert_eq!(vtg.course.to_string(), "54.7");
+    assert_eq!(vtg.course_magnetic, Some(Decimal::new(344, 1)));
+    assert_eq!(vtg.mode, None);
+}
+
+#[test]
+fn gll_position_and_status() {
+    let gll = parse_gll(b"5006.47052,N,01954.18623,E,083559.00,A,A").unwrap();
+    assert!(gll.valid);
+    assert_eq!(gll.latitude.to_string(), "50.1078420");
+    assert_eq!(gll.time, GPSTime { hour: 8, minute: 35, second: 59, millisecond: 0 });
+
+    let gll = parse_gll(b",,,,,V,N").unwrap();
+    assert!(!gll.valid);
+    assert_eq!(gll.mode, Some(PositioningMode::NotValid));
+}
+
+#[test]
+fn zda_date_and_zone() {
+    let zda = parse_zda(b"201530.00,04,07,2002,-03,30").unwrap();
+    assert_eq!((zda.day, zda.month, zda.year), (4, 7, 2002));
+    assert_eq!((zda.zone_hours, zda.zone_minutes), (-3, 30));
+    assert_eq!(zda.time, GPSTime { hour: 20, minute: 15, second: 30, millisecond: 0 });
+
+    assert_eq!(parse_zda(b"201530.00,04,07,02,00,00").unwrap_err(), NmeaError::FieldTooShort);
+    assert_eq!(parse_zda(b"201530.00,04,13,2002,00,00").unwrap_err(), NmeaError::InvalidValue);
+    assert_eq!(parse_zda(b"201530.00,,07,2002,00,00").unwrap_err(), NmeaError::MissingField);
+}
+
+#[test]
+fn talker_is_decoded_separately() {
+    let (talker, sentence) = parse_sentence(b"$GNRMC,083559.00,A,5006.47052,N,01954.18623,E,0.148,,180521,,,A*6E\r\n").unwrap();
+    assert_eq!(talker, TalkerId::GN);
+    assert!(matches!(sentence, Sentence::RMC(rmc) if rmc.valid));
+
+    let (talker, sentence) = parse_sentence(b"$GLGSV,1,1,02,65,32,056,30,72,18,312,*69").unwrap();
+    assert_eq!(talker, TalkerId::GL);
+    assert!(matches!(sentence, Sentence::GSV(gsv) if gsv.satellites()[0].prn == 65));
+
+    let (talker, _) = parse_sentence(b"$GBGSA,A,3,06,09,,,,,,,,,,,1.86,1.01,1.56*12").unwrap();
+    assert_eq!(talker, TalkerId::BD);
+
+    assert_eq!(parse_sentence(b"$PUBX,00,083559.00*33").unwrap_err(), NmeaError::UnknownSentence);
+    assert_eq!(parse_sentence(b"$GPTXT,01,01,02,u-blox ag - www.u-blox.com*50").unwrap_err(), NmeaError::UnknownSentence);
+}
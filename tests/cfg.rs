@@ -0,0 +1,81 @@
+mod common;
+
+use common::*;
+use neo6::ubx::{self, class, encode_frame};
+use neo6::cfg;
+use neo6::{New, NEO6, CfgError, DynamicModel, GPS_Statement};
+
+fn frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; payload.len() + ubx::FRAME_OVERHEAD];
+    let len = encode_frame(class, id, payload, &mut out).unwrap();
+    out.truncate(len);
+    out
+}
+
+fn ack(id: u8) -> Vec<u8> {
+    frame(class::ACK, ubx::ack::ACK, &[class::CFG, id])
+}
+
+fn nak(id: u8) -> Vec<u8> {
+    frame(class::ACK, ubx::ack::NAK, &[class::CFG, id])
+}
+
+#[test]
+fn acknowledged_rate_change() {
+    let mut stream = FIX_RMC.to_vec();
+    stream.extend(ack(cfg::RATE));
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(&stream), MockTx::default());
+    let mut timer = MockTimer::new();
+
+    assert_eq!(neo.set_rate(200, &mut timer, 1000), Ok(()));
+    let sent = frame(class::CFG, cfg::RATE, &[0xC8, 0x00, 0x01, 0x00, 0x01, 0x00]);
+    assert!(neo.tx_mut().unwrap().written.starts_with(&sent));
+    // sentences keep being parsed while waiting
+    assert_eq!(neo.statistics().sentences, 1);
+}
+
+#[test]
+fn rejected_message() {
+    let stream = nak(cfg::NAV5);
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(&stream), MockTx::default());
+    let mut timer = MockTimer::new();
+
+    assert_eq!(neo.set_dynamic_model(DynamicModel::Airborne1g, &mut timer, 1000), Err(CfgError::Nak));
+    assert_eq!(neo.tx_mut().unwrap().written[6 + 2], 6);
+}
+
+#[test]
+fn retries_then_times_out() {
+    // an ACK for another message does not count
+    let stream = ack(cfg::RATE);
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(&stream), MockTx::default());
+    let mut timer = MockTimer::new();
+
+    assert_eq!(neo.set_sentence_rate(GPS_Statement::GSV, 0, &mut timer, 100), Err(CfgError::Timeout));
+    let sent = frame(class::CFG, cfg::MSG, &[0xF0, 0x03, 0x00]);
+    assert_eq!(neo.tx_mut().unwrap().written, [&sent[..], &sent, &sent].concat());
+}
+
+#[test]
+fn save_and_baud_rate_payloads() {
+    assert_eq!(cfg::save(cfg::device::BBR | cfg::device::FLASH)[12], 0x03);
+    let prt = cfg::prt_uart(115_200);
+    assert_eq!(prt[0], 1);
+    assert_eq!(&prt[8..12], &115_200u32.to_le_bytes());
+
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(&[]), MockTx::default());
+    neo.set_baud_rate(115_200).unwrap();
+    assert_eq!(neo.tx_mut().unwrap().written, frame(class::CFG, cfg::PRT, &prt));
+}
+
+#[test]
+fn receive_only_driver_has_no_command_channel() {
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&ack(cfg::RATE)));
+    let mut timer = MockTimer::new();
+    assert_eq!(neo.set_rate(200, &mut timer, 1000), Err(CfgError::NoChannel));
+}
@@ -0,0 +1,143 @@
+// NMEA captures recorded from a NEO-6M module on the tracker's USART3.
+#![allow(dead_code)]
+
+use neo6::LineError;
+use neo6::nmea::checksum;
+
+/// One epoch at 1 Hz with a 3D fix, outdoors.
+pub const FIX_EPOCH: &[u8] = b"\
+$GPRMC,083559.00,A,5006.47052,N,01954.18623,E,0.148,,180521,,,A*70\r\n\
+$GPVTG,,T,,M,0.148,N,0.274,K,A*2F\r\n\
+$GPGGA,083559.00,5006.47052,N,01954.18623,E,1,08,1.01,229.4,M,40.1,M,,*5E\r\n\
+$GPGSA,A,3,10,32,22,27,14,18,01,08,,,,,1.86,1.01,1.56*0F\r\n\
+$GPGSV,3,1,11,01,46,290,28,03,07,020,,08,60,180,33,10,38,067,30*70\r\n\
+$GPGSV,3,2,11,14,20,138,24,18,33,308,31,21,05,210,,22,48,121,35*7E\r\n\
+$GPGSV,3,3,11,27,25,051,29,32,31,089,27,36,24,161,*46\r\n\
+$GPGLL,5006.47052,N,01954.18623,E,083559.00,A,A*6B\r\n";
+
+/// Right after a cold start, before the receiver has any fix.
+pub const COLD_START: &[u8] = b"\
+$GPRMC,,V,,,,,,,,,,N*53\r\n\
+$GPGGA,,,,,,0,00,99.99,,,,,,*48\r\n\
+$GPGSA,A,1,,,,,,,,,,,,,99.99,99.99,99.99*30\r\n\
+$GPGLL,,,,,,V,N*64\r\n";
+
+/// Receiver configured for RMC output only.
+pub const RMC_ONLY: &[u8] = b"\
+$GPRMC,083600.00,A,5006.47061,N,01954.18640,E,0.512,47.21,180521,,,A*5F\r\n\
+$GPRMC,083601.00,A,5006.47070,N,01954.18660,E,0.498,46.80,180521,,,A*55\r\n";
+
+pub const FIX_RMC: &[u8] = b"$GPRMC,083559.00,A,5006.47052,N,01954.18623,E,0.148,,180521,,,A*70\r\n";
+pub const FIX_GGA: &[u8] = b"$GPGGA,083559.00,5006.47052,N,01954.18623,E,1,08,1.01,229.4,M,40.1,M,,*5E\r\n";
+pub const FIX_GSA: &[u8] = b"$GPGSA,A,3,10,32,22,27,14,18,01,08,,,,,1.86,1.01,1.56*0F\r\n";
+
+/// Sentence `$<body>*hh` with its checksum.
+pub fn nmea(body: &str) -> Vec<u8> {
+    format!("${}*{:02X}\r\n", body, checksum(body.as_bytes())).into_bytes()
+}
+
+/// RMC with the fix of `FIX_RMC` at `time` (hhmmss) on `date` (ddmmyy).
+pub fn rmc_at(time: &str, date: &str) -> Vec<u8> {
+    nmea(&format!("GPRMC,{}.00,A,5006.47052,N,01954.18623,E,0.148,,{},,,A", time, date))
+}
+
+/// Splits a capture into its `\r\n` terminated sentences.
+pub fn sentences(capture: &'static [u8]) -> impl Iterator<Item = &'static [u8]> {
+    capture.split_inclusive(|c| *c == b'\n')
+}
+
+/// Serial receiver replaying a capture byte by byte.
+pub struct MockRx {
+    data: Vec<u8>,
+    pos: usize,
+    /// Receive errors reported before the byte at the given position.
+    errors: Vec<(usize, LineError)>,
+}
+
+impl MockRx {
+    pub fn new(data: &[u8]) -> Self {
+        MockRx { data: data.to_vec(), pos: 0, errors: Vec::new() }
+    }
+    pub fn with_error(mut self, at: usize, error: LineError) -> Self {
+        self.errors.push((at, error));
+        self
+    }
+    pub fn is_done(&self) -> bool {
+        self.pos == self.data.len()
+    }
+}
+
+impl embedded_hal::serial::Read<u8> for MockRx {
+    type Error = LineError;
+    fn read(&mut self) -> nb::Result<u8, LineError> {
+        if let Some(i) = self.errors.iter().position(|(at, _)| *at == self.pos) {
+            return Err(nb::Error::Other(self.errors.remove(i).1));
+        }
+        match self.data.get(self.pos) {
+            Some(c) => {
+                self.pos += 1;
+                Ok(*c)
+            }
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+/// Serial transmitter recording everything written to it.
+#[derive(Default)]
+pub struct MockTx {
+    pub written: Vec<u8>,
+}
+
+impl embedded_hal::serial::Write<u8> for MockTx {
+    type Error = ();
+    fn write(&mut self, word: u8) -> nb::Result<(), ()> {
+        self.written.push(word);
+        Ok(())
+    }
+    fn flush(&mut self) -> nb::Result<(), ()> {
+        Ok(())
+    }
+}
+
+/// Count-down timer expiring after a number of `wait()` polls.
+pub struct MockTimer {
+    left: u32,
+}
+
+impl MockTimer {
+    pub fn new() -> Self {
+        MockTimer { left: 0 }
+    }
+}
+
+impl embedded_hal::timer::CountDown for MockTimer {
+    type Time = u32;
+    fn start<T: Into<u32>>(&mut self, count: T) {
+        self.left = count.into();
+    }
+    fn wait(&mut self) -> nb::Result<(), void::Void> {
+        if self.left == 0 {
+            return Ok(());
+        }
+        self.left -= 1;
+        Err(nb::Error::WouldBlock)
+    }
+}
+
+/// Real-time clock the test moves by hand.
+#[derive(Default)]
+pub struct MockRtc {
+    pub seconds: u32,
+    pub writes: u32,
+}
+
+impl neo6::RealTimeClock for MockRtc {
+    fn seconds(&self) -> u32 {
+        self.seconds
+    }
+    fn set_seconds(&mut self, seconds: u32) {
+        self.seconds = seconds;
+        self.writes += 1;
+    }
+}
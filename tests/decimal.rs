@@ -0,0 +1,81 @@
+use neo6::{Decimal, NmeaError};
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn display_round_trips_fields() {
+    for text in ["12.05", "12.5", "12.50", "0.148", "-12.3", "229.4", "99.99", "7", "-0.005"] {
+        assert_eq!(dec(text).to_string(), text);
+    }
+}
+
+#[test]
+fn keeps_leading_zeros_and_sign() {
+    assert_ne!(dec("12.05"), dec("12.5"));
+    assert_eq!(dec("12.5"), dec("12.50"));
+    assert_eq!(dec("-3.2").value(), -32);
+    assert!(dec("-3.2").is_negative());
+    assert_eq!(dec("12.05").scale(), 2);
+    assert_eq!(dec("-12.75").trunc(), -12);
+}
+
+#[test]
+fn arithmetic_and_ordering() {
+    assert_eq!(dec("1.5") + dec("0.25"), dec("1.75"));
+    assert_eq!(dec("1.5") - dec("2"), dec("-0.5"));
+    assert_eq!(dec("1.5") * dec("-2.5"), dec("-3.75"));
+    assert_eq!(-dec("1.5"), dec("-1.5"));
+    assert!(dec("1.01") < dec("1.1"));
+    assert!(dec("-1") < dec("0.001"));
+    assert_eq!(dec("2.345").rescale(2), Some(dec("2.35")));
+    assert_eq!(dec("-2.345").rescale(1), Some(dec("-2.3")));
+    assert_eq!(Decimal::new(i32::MAX, 0).checked_add(Decimal::from(1)), None);
+}
+
+#[test]
+fn operators_saturate() {
+    let max = Decimal::new(i32::MAX, 2);
+    let min = Decimal::new(i32::MIN, 2);
+    // decimal places go first
+    assert_eq!((max + dec("0.01")).to_string(), "21474836.5");
+    assert_eq!((min - dec("0.01")).to_string(), "-21474836.5");
+    assert_eq!((max * dec("-2")).to_string(), "-42949672.9");
+    assert_eq!((-min).to_string(), "21474836.5");
+    assert_eq!((Decimal::new(i32::MAX, 0) + Decimal::new(1, 9)).to_string(), "2147483647");
+    // then the magnitude
+    assert_eq!(Decimal::new(i32::MAX, 0) + dec("1"), Decimal::new(i32::MAX, 0));
+    assert_eq!(Decimal::new(i32::MIN, 0) * dec("3"), Decimal::new(i32::MIN, 0));
+    assert_eq!(min.checked_neg(), None);
+    assert_eq!(min.checked_sub(dec("0.01")), None);
+    assert_eq!(min.checked_mul(dec("-1")), None);
+}
+
+#[test]
+fn negative_zero_keeps_its_sign() {
+    assert_eq!(dec("-0.0").to_string(), "-0.0");
+    assert_eq!(dec("-0.0"), Decimal::ZERO);
+    assert!(!dec("-0.0").is_negative());
+    assert_eq!((-dec("0.00")).to_string(), "-0.00");
+    assert_eq!((-dec("-0.00")).to_string(), "0.00");
+    assert_eq!((dec("-0.0") + dec("0.0")).to_string(), "0.0");
+}
+
+#[test]
+fn float_conversion() {
+    assert!((dec("229.4").to_f32() - 229.4).abs() < 1e-4);
+    assert!((dec("-0.148").to_f64() + 0.148).abs() < 1e-12);
+}
+
+#[test]
+fn rejects_malformed_fields() {
+    assert_eq!(Decimal::parse(b"").unwrap_err(), NmeaError::MissingField);
+    assert_eq!(Decimal::parse(b"1.2.3").unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(Decimal::parse(b"1a").unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(Decimal::parse(b".").unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(Decimal::parse(b"12.").unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(Decimal::parse(b"-.").unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(Decimal::parse(b"99999999999").unwrap_err(), NmeaError::BadDigit);
+    assert_eq!(Decimal::parse(b"0.0000000001").unwrap_err(), NmeaError::BadDigit);
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use std::ptr;
+
+use common::*;
+use neo6::{NEO6, RingBuffer, CircularReader, LineError};
+
+/// DMA channel in circular mode: writes bytes, counts NDTR down and raises
+/// the half-transfer and transfer-complete events.
+struct Dma {
+    buffer: *mut u8,
+    ndtr: usize,
+    half: bool,
+    complete: bool,
+}
+
+impl Dma {
+    fn new() -> (Self, CircularReader) {
+        let buffer: &'static mut [u8] = Box::leak(Box::new([0u8; 64]));
+        let at = buffer.as_mut_ptr();
+        let reader = CircularReader::new(buffer).unwrap();
+        (Dma { buffer: at, ndtr: 64, half: false, complete: false }, reader)
+    }
+    fn write(&mut self, data: &[u8]) {
+        for byte in data {
+            let at = 64 - self.ndtr;
+            unsafe { ptr::write_volatile(self.buffer.add(at), *byte) };
+            self.half |= at == 31;
+            self.complete |= at == 63;
+            self.ndtr -= 1;
+            if self.ndtr == 0 {
+                self.ndtr = 64;
+            }
+        }
+    }
+    /// Reports the events since the last call, as the DMA interrupt does.
+    fn interrupt(&mut self, reader: &mut CircularReader) {
+        if std::mem::take(&mut self.half) {
+            reader.half_transfer();
+        }
+        if std::mem::take(&mut self.complete) {
+            reader.transfer_complete();
+        }
+    }
+    /// Reads everything new in chunks, after the events.
+    fn read(&mut self, reader: &mut CircularReader) -> Result<Vec<u8>, LineError> {
+        self.interrupt(reader);
+        let mut out = Vec::new();
+        let mut chunk = [0u8; 16];
+        loop {
+            match reader.read(self.ndtr, &mut chunk)? {
+                0 => return Ok(out),
+                n => out.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+}
+
+#[test]
+fn reads_up_to_write_position_across_wrap() {
+    let (mut dma, mut reader) = Dma::new();
+    dma.write(&[1; 40]);
+    assert_eq!(dma.read(&mut reader).unwrap(), vec![1; 40]);
+    let data = (0..40).collect::<Vec<u8>>();
+    dma.write(&data);
+    assert_eq!(dma.read(&mut reader).unwrap(), data);
+    assert_eq!(reader.position(), 16);
+    // nothing new
+    assert_eq!(dma.read(&mut reader).unwrap(), vec![]);
+    assert_eq!(reader.overruns(), 0);
+
+    let empty: &'static mut [u8] = Box::leak(Box::new([]));
+    assert!(CircularReader::new(empty).is_none());
+}
+
+#[test]
+fn lapped_reader_reports_an_overrun() {
+    let (mut dma, mut reader) = Dma::new();
+    dma.write(&[1; 20]);
+    assert_eq!(dma.read(&mut reader).unwrap().len(), 20);
+    // a full pass and a bit more before the reader gets to run
+    dma.write(&[2; 70]);
+    assert_eq!(dma.read(&mut reader), Err(LineError::Overrun));
+    assert_eq!(reader.position(), 26);
+    assert_eq!(reader.overruns(), 1);
+
+    dma.write(b"$GP");
+    assert_eq!(dma.read(&mut reader).unwrap(), b"$GP");
+
+    // the event raised just after the read is no overrun
+    dma.write(&[3; 3]);
+    reader.read(dma.ndtr, &mut [0; 16]).unwrap();
+    assert_eq!(dma.read(&mut reader).unwrap(), vec![]);
+    assert_eq!(reader.overruns(), 1);
+}
+
+#[test]
+fn chunks_read_after_each_event_feed_the_driver() {
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    let (mut dma, mut reader) = Dma::new();
+
+    // the buffer is read out after every part, as when the main loop wakes
+    // on an idle line or a half-transfer or transfer-complete event
+    for sentence in sentences(FIX_EPOCH) {
+        for part in sentence.chunks(32) {
+            dma.write(part);
+            neo.feed(&dma.read(&mut reader).unwrap());
+        }
+    }
+
+    assert!(neo.data_valid());
+    assert_eq!(neo.statistics().sentences, 8);
+    assert_eq!(neo.statistics().errors(), 0);
+}
+
+#[test]
+fn drain_queues_the_overrun() {
+    let mut queue = [0u8; 512];
+    let mut ring = RingBuffer::new(&mut queue);
+    let (mut producer, consumer) = ring.split();
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::receive_only(&mut buf, consumer);
+    let (mut dma, mut reader) = Dma::new();
+
+    // the start of a sentence, then a whole pass of the buffer unread
+    dma.write(&FIX_RMC[..30]);
+    dma.interrupt(&mut reader);
+    reader.drain(dma.ndtr, &mut producer);
+    dma.write(&[b'x'; 64]);
+    dma.interrupt(&mut reader);
+    reader.drain(dma.ndtr, &mut producer);
+    dma.write(FIX_GSA);
+    dma.interrupt(&mut reader);
+    reader.drain(dma.ndtr, &mut producer);
+    neo.poll();
+
+    assert_eq!(neo.statistics().overruns, 1);
+    assert_eq!(neo.statistics().sentences, 1);
+    assert_eq!(neo.statistics().overlong_lines, 0);
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use common::*;
+use neo6::{New, NEO6, FixType, GPSTime, TalkerId, TapMode, SentenceSet, GPS_Statement, LineError};
+
+fn run<Log: embedded_hal::serial::Write<u8>>(neo: &mut NEO6<MockRx, MockTx, Log>) {
+    while !neo.rx_mut().is_done() {
+        neo.receive();
+        neo.write_tap();
+    }
+}
+
+#[test]
+fn fix_epoch_over_mock_serial() {
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(FIX_EPOCH), MockTx::default()).with_log(MockTx::default());
+    run(&mut neo);
+
+    assert!(neo.data_valid());
+    let data = neo.get_data();
+    assert_eq!(data.get_fix_type(), FixType::GPSFix);
+    assert_eq!(data.satellites_no(), 8);
+    // the tap is off by default and nothing is sent to the module
+    assert!(neo.log_mut().unwrap().written.is_empty());
+    assert!(neo.tx_mut().unwrap().written.is_empty());
+}
+
+#[test]
+fn cold_start_over_mock_serial() {
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(COLD_START), MockTx::default());
+    run(&mut neo);
+
+    assert!(!neo.data_valid());
+}
+
+#[test]
+fn report_goes_to_log() {
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(FIX_EPOCH), MockTx::default()).with_log(MockTx::default());
+    run(&mut neo);
+    neo.report();
+
+    assert!(neo.tx_mut().unwrap().written.is_empty());
+    let text = String::from_utf8(neo.log_mut().unwrap().written.clone()).unwrap();
+    assert!(text.starts_with("08:35:59\n18/05/21\n"));
+}
+
+#[test]
+fn corrupt_sentence_is_dropped_and_counted() {
+    const CORRUPT: &[u8] = b"\
+$GPGGA,083559.00,5007.47052,N,01954.18623,E,1,08,1.01,229.4,M,40.1,M,,*5E\r\n\
+$GPGSA,A,3,10,32,22,27,14,18,01,08,,,,,1.86,1.01,1.56*0F\r\n";
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(CORRUPT), MockTx::default());
+    run(&mut neo);
+
+    let stats = neo.statistics();
+    assert_eq!(stats.checksum_errors, 1);
+    assert_eq!(stats.sentences, 1);
+    assert_eq!(neo.get_data().get_fix_type(), FixType::NoFix);
+}
+
+#[test]
+fn unknown_and_malformed_sentences_are_counted() {
+    const MIXED: &[u8] = b"\
+$GPTXT,01,01,02,u-blox ag - www.u-blox.com*50\r\n\
+$GPGGA,0835*74\r\n\
+$GPGSA,A,3,10,32,22,27,14,18,01,08,,,,,1.86,1.01,1.56*0F\r\n";
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(MIXED), MockTx::default());
+    run(&mut neo);
+
+    let stats = neo.statistics();
+    assert_eq!(stats.unknown_sentences, 1);
+    assert_eq!(stats.parse_errors, 1);
+    assert_eq!(stats.sentences, 1);
+}
+
+#[test]
+fn line_error_drops_broken_sentence() {
+    // framing error in the middle of the GGA
+    let at = FIX_EPOCH.windows(6).position(|w| w == b"$GPGGA").unwrap() + 20;
+    let rx = MockRx::new(FIX_EPOCH).with_error(at, LineError::Framing);
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, rx, MockTx::default());
+    run(&mut neo);
+    neo.end_epoch();
+
+    let stats = neo.statistics();
+    assert_eq!(stats.framing_errors, 1);
+    assert_eq!(stats.line_errors(), 1);
+    // the rest of the GGA is skipped, not reported as a broken sentence
+    assert_eq!(stats.sentences, 7);
+    assert_eq!(stats.errors(), 1);
+    assert_eq!(neo.get_data().satellites_no(), 0);
+    assert!(neo.data_valid());
+}
+
+#[test]
+fn rmc_only_receiver() {
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(RMC_ONLY), MockTx::default());
+    neo.set_epoch_sentences(SentenceSet::EMPTY.with(GPS_Statement::RMC));
+    run(&mut neo);
+
+    assert!(neo.data_valid());
+    let data = neo.get_data();
+    assert_eq!(data.get_time(), GPSTime { hour: 8, minute: 36, second: 1, millisecond: 0 });
+    assert_eq!(data.get_position().latitude.to_string(), "50.1078450");
+    assert_eq!(data.get_fix_type(), FixType::GPSFix);
+}
+
+#[test]
+fn sky_view_from_gsv() {
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(FIX_EPOCH), MockTx::default());
+    run(&mut neo);
+
+    let data = neo.get_data();
+    let sky = data.get_sky_view();
+    assert_eq!(sky.len(), 11);
+    assert_eq!(sky.tracked(), 8);
+    let used: Vec<u8> = sky.satellites().iter().filter(|s| s.used).map(|s| s.prn).collect();
+    assert_eq!(used, [1, 8, 10, 14, 18, 22, 27, 32]);
+    let sat = sky.get(22).unwrap();
+    assert_eq!((sat.elevation, sat.azimuth, sat.snr), (Some(48), Some(121), Some(35)));
+    assert!(!sky.get(3).unwrap().used);
+    assert_eq!(neo.statistics().parse_errors, 0);
+}
+
+#[test]
+fn speed_in_kmh_and_full_year() {
+    const ZDA_EPOCH: &[u8] = b"\
+$GPVTG,,T,,M,0.148,N,0.274,K,A*2F\r\n\
+$GPZDA,083559.00,18,05,2021,00,00*69\r\n";
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(ZDA_EPOCH), MockTx::default());
+    run(&mut neo);
+    neo.end_epoch();
+
+    let data = neo.get_data();
+    assert_eq!(data.get_speed_kmh().to_string(), "0.274");
+    assert_eq!(data.get_full_year(), Some(2021));
+    assert_eq!(data.get_date().month, 5);
+    assert_eq!(neo.statistics().sentences, 2);
+}
+
+#[test]
+fn multi_gnss_talkers() {
+    const GNSS: &[u8] = b"\
+$GNGGA,083559.00,5006.47052,N,01954.18623,E,1,12,0.78,229.4,M,40.1,M,,*44\r\n\
+$GPGSV,1,1,03,10,38,067,30,22,48,121,35,32,31,089,27*4D\r\n\
+$GLGSV,1,1,02,65,32,056,30,72,18,312,*69\r\n\
+$GPGSV,1,1,03,10,38,067,30,22,48,121,35,32,31,089,27*4D\r\n";
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(GNSS), MockTx::default());
+    run(&mut neo);
+    neo.end_epoch();
+
+    let data = neo.get_data();
+    assert_eq!(data.get_talker(), TalkerId::GN);
+    assert_eq!(data.satellites_no(), 12);
+    let sky = data.get_sky_view();
+    assert_eq!(sky.len(), 5);
+    assert_eq!(sky.get(65).unwrap().talker, TalkerId::GL);
+    assert_eq!(sky.get(22).unwrap().talker, TalkerId::GP);
+    assert_eq!(neo.statistics().unknown_sentences, 0);
+}
+
+#[test]
+fn raw_tap_passes_everything() {
+    let mut stream = FIX_EPOCH.to_vec();
+    stream.extend_from_slice(b"$GPRMC,083600.00,A*00\r\n");
+    let mut buf = [0u8; 200];
+    let mut tap = [0u8; 1024];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(&stream), MockTx::default()).with_log(MockTx::default());
+    neo.set_tap(TapMode::Raw, &mut tap);
+    run(&mut neo);
+
+    assert_eq!(neo.log_mut().unwrap().written, stream);
+}
+
+#[test]
+fn filtered_tap_and_full_queue() {
+    let mut buf = [0u8; 200];
+    let mut tap = [0u8; 1024];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(FIX_EPOCH), MockTx::default()).with_log(MockTx::default());
+    let set = SentenceSet::EMPTY.with(GPS_Statement::RMC).with(GPS_Statement::GGA);
+    neo.set_tap(TapMode::Filtered(set), &mut tap);
+    run(&mut neo);
+    assert_eq!(neo.log_mut().unwrap().written, [FIX_RMC, FIX_GGA].concat());
+
+    // a tap that cannot keep up drops whole sentences
+    let mut tap = [0u8; 100];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(FIX_EPOCH), MockTx::default()).with_log(BusyTx);
+    neo.set_tap(TapMode::Raw, &mut tap);
+    run(&mut neo);
+    assert!(neo.data_valid());
+    assert_eq!(neo.statistics().tap_dropped, 7);
+}
+
+/// Transmitter that is never ready.
+struct BusyTx;
+
+impl embedded_hal::serial::Write<u8> for BusyTx {
+    type Error = ();
+    fn write(&mut self, _: u8) -> nb::Result<(), ()> {
+        Err(nb::Error::WouldBlock)
+    }
+    fn flush(&mut self) -> nb::Result<(), ()> {
+        Err(nb::Error::WouldBlock)
+    }
+}
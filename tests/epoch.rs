@@ -0,0 +1,74 @@
+mod common;
+
+use common::*;
+use neo6::{NEO6, GPSTime, GPS_Statement, SentenceSet};
+
+#[test]
+fn epoch_is_published_once_complete() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    let mut epoch = sentences(FIX_EPOCH).collect::<Vec<_>>();
+    let gll = epoch.pop().unwrap();
+    for sentence in epoch {
+        neo.feed(sentence);
+    }
+
+    assert!(!neo.epoch_ready());
+    assert_eq!(neo.get_data().get_epoch(), 0);
+    assert_eq!(neo.get_pending().satellites_no(), 8);
+
+    neo.feed(gll);
+    assert!(neo.epoch_ready());
+    let data = neo.take_epoch().unwrap();
+    assert_eq!(data.get_time(), GPSTime { hour: 8, minute: 35, second: 59, millisecond: 0 });
+    assert_eq!(data.satellites_no(), 8);
+    assert!(data.has_fix());
+    assert!(neo.take_epoch().is_none());
+}
+
+#[test]
+fn next_epoch_publishes_the_previous_one() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(FIX_EPOCH);
+    assert!(neo.take_epoch().is_some());
+    // the GGA of 08:36:00 never arrives
+    neo.feed(RMC_ONLY);
+
+    let data = neo.take_epoch().unwrap();
+    assert_eq!(data.get_time(), GPSTime { hour: 8, minute: 36, second: 0, millisecond: 0 });
+    assert_eq!(data.get_position().latitude.to_string(), "50.1078435");
+    assert_eq!(neo.get_pending().get_time(), GPSTime { hour: 8, minute: 36, second: 1, millisecond: 0 });
+    assert_eq!(neo.get_data().get_time(), data.get_time());
+}
+
+#[test]
+fn configured_sentences_complete_the_epoch() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.set_epoch_sentences(SentenceSet::EMPTY.with(GPS_Statement::RMC).with(GPS_Statement::GGA));
+    neo.feed(FIX_RMC);
+    assert!(!neo.epoch_ready());
+    neo.feed(FIX_GGA);
+    assert!(neo.epoch_ready());
+    // late sentences of the same epoch do not publish it again
+    neo.feed(FIX_GSA);
+    neo.take_epoch();
+    neo.end_epoch();
+    assert!(!neo.epoch_ready());
+}
+
+#[test]
+fn gsa_of_each_system_merge_within_the_epoch() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(&rmc_at("083559", "180521"));
+    neo.feed(&nmea("GNGSA,A,3,10,32,22,27,,,,,,,,,1.86,1.01,1.56"));
+    neo.feed(&nmea("GNGSA,A,3,65,71,10,,,,,,,,,,1.86,1.01,1.56"));
+    assert_eq!(neo.get_pending().get_satellite_ids(), [10, 32, 22, 27, 65, 71, 0, 0, 0, 0, 0, 0]);
+
+    // the next epoch starts over
+    neo.feed(&rmc_at("083600", "180521"));
+    neo.feed(&nmea("GNGSA,A,3,14,18,,,,,,,,,,,1.86,1.01,1.56"));
+    assert_eq!(neo.get_pending().get_satellite_ids(), [14, 18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::*;
+use neo6::{NEO6, Pps, PpsTime, UtcDateTime};
+
+const HZ: u32 = 72_000_000;
+// the counter wraps within the first second
+const START: u32 = u32::MAX - 100_000_000;
+
+fn second(second: u8) -> UtcDateTime {
+    UtcDateTime::new(2021, 5, 18, 8, 36, second, 0).unwrap()
+}
+
+#[test]
+fn edges_take_the_next_second() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.pps_mut().set_nominal(HZ);
+
+    // no label without valid data
+    neo.pps_capture(START - HZ);
+    neo.feed(COLD_START);
+    assert_eq!(neo.pps().edges(), 0);
+
+    neo.pps_capture(START);
+    neo.feed(&rmc_at("083559", "180521"));
+    assert_eq!(neo.pps().last_edge(), Some((START, 1_621_326_959)));
+    let half = neo.pps_now(START.wrapping_add(HZ / 2)).unwrap();
+    assert_eq!(half, PpsTime { seconds: 1_621_326_959, nanos: 500_000_000 });
+    assert_eq!(half.utc().unwrap().to_string(), "2021-05-18T08:35:59.500Z");
+    assert_eq!(neo.pps().frequency(), None);
+
+    // 500 ppb fast
+    let next = START.wrapping_add(HZ + 36);
+    neo.pps_capture(next);
+    neo.feed(&nmea("GPZDA,083600.00,18,05,2021,00,00"));
+    assert_eq!(neo.pps().edges(), 2);
+    assert_eq!(neo.pps().frequency(), Some(HZ + 36));
+    assert_eq!(neo.pps().offset_ppb(), Some(500));
+    assert_eq!(neo.pps().drift_ppb(), None);
+    let now = neo.pps_now(next.wrapping_add(7200)).unwrap();
+    assert_eq!(now.nanos, 99_999);
+    assert_eq!(now.unix_micros(), 1_621_326_960_000_099);
+
+    // the RMC of the same second finds no edge left
+    neo.feed(&rmc_at("083600", "180521"));
+    assert_eq!(neo.pps().edges(), 2);
+    assert_eq!(neo.pps().rejected(), 0);
+}
+
+#[test]
+fn gaps_and_glitches() {
+    let mut pps = Pps::new();
+    pps.set_nominal(HZ);
+    pps.capture(START);
+    assert!(pps.label(second(0)));
+
+    // the sentences of 08:36:01 were lost
+    pps.capture(START.wrapping_add(HZ + 72));
+    let edge = START.wrapping_add(2 * (HZ + 72));
+    pps.capture(edge);
+    assert!(pps.label(second(2)));
+    assert_eq!(pps.offset_ppb(), Some(1000));
+
+    let edge = edge.wrapping_add(HZ + 108);
+    pps.capture(edge);
+    assert!(pps.label(second(3)));
+    assert_eq!(pps.offset_ppb(), Some(1500));
+    assert_eq!(pps.drift_ppb(), Some(500));
+
+    // a spike half way through the second
+    pps.capture(edge.wrapping_add(HZ / 2));
+    assert!(!pps.label(second(4)));
+    assert_eq!(pps.rejected(), 1);
+    assert_eq!(pps.last_edge().unwrap().0, edge);
+
+    // times off the second leave the edge pending
+    pps.capture(edge.wrapping_add(HZ));
+    let late = UtcDateTime::new(2021, 5, 18, 8, 36, 4, 200).unwrap();
+    assert!(!pps.label(late));
+    assert!(pps.label(second(4)));
+    assert_eq!(pps.edges(), 4);
+}
+
+#[test]
+fn holdover_ends_the_time() {
+    let mut pps = Pps::new();
+    pps.capture(START);
+    pps.label(second(0));
+    // neither measured nor nominal rate
+    assert_eq!(pps.now(START), None);
+
+    pps.set_nominal(HZ);
+    pps.set_holdover(3);
+    assert_eq!(pps.now(START.wrapping_add(3 * HZ - 1)).unwrap().seconds, 1_621_326_962);
+    assert_eq!(pps.now(START.wrapping_add(3 * HZ)), None);
+    // before the edge
+    assert_eq!(pps.now(START - 1), None);
+}
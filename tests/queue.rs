@@ -0,0 +1,106 @@
+mod common;
+
+use std::thread;
+
+use common::*;
+use embedded_hal::serial::Read;
+use neo6::{NEO6, RingBuffer, LineError};
+
+#[test]
+fn push_pop_and_overflow() {
+    let mut buf = [0u8; 4];
+    let mut ring = RingBuffer::new(&mut buf);
+    assert_eq!(ring.capacity(), 3);
+    let (mut producer, mut consumer) = ring.split();
+
+    for round in 0..5u8 {
+        assert!(producer.push(round));
+        assert!(producer.push(round + 1));
+        assert_eq!(consumer.len(), 2);
+        assert_eq!(consumer.pop(), Some(round));
+        assert_eq!(consumer.pop(), Some(round + 1));
+        assert_eq!(consumer.pop(), None);
+    }
+    assert!(producer.push(1) && producer.push(2) && producer.push(3));
+    assert!(!producer.push(4));
+    assert_eq!(consumer.overflows(), 1);
+    assert_eq!(consumer.pop(), Some(1));
+}
+
+#[test]
+fn producer_and_consumer_on_separate_threads() {
+    const COUNT: u32 = 10_000;
+    let mut buf = [0u8; 64];
+    let mut ring = RingBuffer::new(&mut buf);
+    let (mut producer, mut consumer) = ring.split();
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            for i in 0..COUNT {
+                while !producer.push(i as u8) {
+                    thread::yield_now();
+                }
+            }
+        });
+        let mut expected = 0u32;
+        while expected < COUNT {
+            if let Some(byte) = consumer.pop() {
+                assert_eq!(byte, expected as u8);
+                expected += 1;
+            }
+        }
+    });
+    assert!(ring.is_empty());
+}
+
+#[test]
+fn driver_reads_from_consumer() {
+    let mut queue = [0u8; 1024];
+    let mut ring = RingBuffer::new(&mut queue);
+    let (mut producer, consumer) = ring.split();
+    // the whole epoch arrives before the main loop gets to run
+    producer.receive(&mut MockRx::new(FIX_EPOCH));
+
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::receive_only(&mut buf, consumer);
+    neo.poll();
+
+    assert!(neo.data_valid());
+    assert_eq!(neo.statistics().sentences, 8);
+    assert_eq!(neo.statistics().errors(), 0);
+    assert_eq!(neo.rx_mut().overflows(), 0);
+}
+
+#[test]
+fn full_queue_counts_dropped_bytes() {
+    let mut queue = [0u8; 101];
+    let mut ring = RingBuffer::new(&mut queue);
+    let (mut producer, consumer) = ring.split();
+    producer.receive(&mut MockRx::new(FIX_EPOCH));
+    assert_eq!(producer.overflows() as usize, FIX_EPOCH.len() - 100);
+
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::receive_only(&mut buf, consumer);
+    neo.poll();
+    let complete = sentences(FIX_EPOCH).scan(0, |end, s| { *end += s.len(); Some(*end) }).filter(|end| *end <= 100).count();
+    assert_eq!(neo.statistics().sentences as usize, complete);
+    assert_eq!(neo.statistics().queue_overflows, 1);
+    assert_eq!(neo.statistics().errors(), 1);
+}
+
+#[test]
+fn receive_errors_keep_their_place() {
+    let mut queue = [0u8; 16];
+    let mut ring = RingBuffer::new(&mut queue);
+    let (mut producer, mut consumer) = ring.split();
+    producer.receive(&mut MockRx::new(b"ab").with_error(2, LineError::Overrun));
+    // the consumer has not caught up with the first error yet
+    assert!(!producer.push_error(LineError::Noise));
+    producer.push(b'c');
+
+    assert_eq!(consumer.read(), Ok(b'a'));
+    assert_eq!(consumer.read(), Ok(b'b'));
+    assert_eq!(consumer.read(), Err(nb::Error::Other(LineError::Overrun)));
+    assert_eq!(consumer.read(), Ok(b'c'));
+    assert_eq!(consumer.read(), Err(nb::Error::WouldBlock));
+}
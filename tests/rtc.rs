@@ -0,0 +1,92 @@
+mod common;
+
+use common::*;
+use neo6::{NEO6, GPS_Data, GPS_Statement, RealTimeClock, RtcSync, SentenceSet};
+
+/// Epoch with a fix at `time` (hhmmss) on `date` (ddmmyy).
+fn fix_at(time: &str, date: &str) -> GPS_Data {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.set_epoch_sentences(SentenceSet::EMPTY.with(GPS_Statement::RMC));
+    neo.feed(&rmc_at(time, date));
+    neo.take_epoch().unwrap()
+}
+
+#[test]
+fn first_fix_sets_the_clock() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    let mut rtc = MockRtc::default();
+    let mut sync = RtcSync::new();
+
+    neo.feed(COLD_START);
+    assert!(!sync.update(&mut rtc, &neo.get_pending()));
+    assert_eq!(sync.since_sync(&rtc), None);
+    assert_eq!(rtc.writes, 0);
+
+    neo.feed(FIX_EPOCH);
+    assert!(sync.update(&mut rtc, &neo.get_data()));
+    assert_eq!(rtc.seconds, 1_621_326_959);
+    assert_eq!(rtc.utc().unwrap().to_string(), "2021-05-18T08:35:59.000Z");
+    assert_eq!(sync.last_sync(), Some(1_621_326_959));
+    assert_eq!(sync.offset(), None);
+    assert_eq!(sync.drift_ppm(), None);
+
+    // not due again within the interval
+    rtc.seconds += 10;
+    assert!(!sync.update(&mut rtc, &fix_at("083609", "180521")));
+    assert_eq!(sync.since_sync(&rtc), Some(10));
+    assert_eq!(rtc.writes, 1);
+}
+
+#[test]
+fn drift_is_measured_between_syncs() {
+    let mut rtc = MockRtc::default();
+    let mut sync = RtcSync::new();
+    assert!(sync.update(&mut rtc, &fix_at("233559", "180521")));
+
+    // the clock gained 2 s over an hour, across midnight
+    rtc.seconds += 3602;
+    assert!(sync.update(&mut rtc, &fix_at("003559", "190521")));
+    assert_eq!(sync.offset(), Some(2));
+    assert_eq!(sync.drift_ppm(), Some(555));
+    assert_eq!(rtc.utc().unwrap().to_string(), "2021-05-19T00:35:59.000Z");
+    assert_eq!(sync.since_sync(&rtc), Some(0));
+
+    // then lost 1 s over two hours
+    sync.set_interval(7200);
+    rtc.seconds += 7199;
+    assert!(sync.update(&mut rtc, &fix_at("023559", "190521")));
+    assert_eq!(sync.offset(), Some(-1));
+    assert_eq!(sync.drift_ppm(), Some(-138));
+    assert_eq!(sync.syncs(), 3);
+}
+
+#[test]
+fn time_jumps_start_over() {
+    let mut rtc = MockRtc::default();
+    let mut sync = RtcSync::new();
+    assert!(sync.update(&mut rtc, &fix_at("083559", "180521")));
+    rtc.seconds += 3600;
+    assert!(sync.update(&mut rtc, &fix_at("093559", "180521")));
+    assert_eq!(sync.drift_ppm(), Some(0));
+
+    // the receiver comes back from a reset a day early
+    rtc.seconds += 10;
+    assert!(sync.update(&mut rtc, &fix_at("093609", "170521")));
+    assert_eq!(rtc.utc().unwrap().to_string(), "2021-05-17T09:36:09.000Z");
+    assert_eq!(sync.offset(), None);
+    assert_eq!(sync.drift_ppm(), None);
+
+    // and then gets the date right: far more than the clock can drift
+    rtc.seconds += 3600;
+    assert!(sync.update(&mut rtc, &fix_at("103609", "180521")));
+    assert_eq!(rtc.utc().unwrap().to_string(), "2021-05-18T10:36:09.000Z");
+    assert_eq!(sync.drift_ppm(), None);
+
+    // from there on the drift is measured again
+    rtc.seconds += 3601;
+    assert!(sync.update(&mut rtc, &fix_at("113609", "180521")));
+    assert_eq!(sync.drift_ppm(), Some(277));
+    assert_eq!(sync.syncs(), 5);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::*;
+use neo6::nmea::parse_time;
+use neo6::{NEO6, GPSDate, GPSTime, NmeaError, UtcDateTime, Weekday};
+
+#[test]
+fn time_keeps_the_fraction() {
+    assert_eq!(parse_time(b"083559.257").unwrap().millisecond, 257);
+    assert_eq!(parse_time(b"083559.5").unwrap().millisecond, 500);
+    assert_eq!(parse_time(b"083559").unwrap().millisecond, 0);
+    assert_eq!(parse_time(b"083559.2a"), Err(NmeaError::BadDigit));
+    assert_eq!(parse_time(b"083559,00"), Err(NmeaError::BadDigit));
+    let time = GPSTime { hour: 9, minute: 5, second: 3, millisecond: 0 };
+    assert_eq!(time.to_string(), "09:05:03");
+    assert_eq!(GPSDate { day: 1, month: 2, year: 3 }.to_string(), "01/02/03");
+}
+
+#[test]
+fn utc_from_fix_epoch() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(FIX_EPOCH);
+
+    let utc = neo.get_data().get_utc().unwrap();
+    assert_eq!(utc.to_string(), "2021-05-18T08:35:59.000Z");
+    assert_eq!(utc.weekday(), Weekday::Tuesday);
+    assert_eq!(utc.day_of_year(), 138);
+    assert_eq!(utc.unix_seconds(), 1_621_326_959);
+    assert_eq!(UtcDateTime::from_unix_seconds(1_621_326_959), Some(utc));
+    // no date yet
+    assert_eq!(neo6::GPS_Data::new().get_utc(), None);
+}
+
+#[test]
+fn year_rolls_over_after_zda() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(&nmea("GPZDA,235959.00,31,12,2026,00,00"));
+    neo.feed(&nmea("GPRMC,000000.00,A,5006.47052,N,01954.18623,E,0.148,,010127,,,A"));
+    let utc = neo.get_pending().get_utc().unwrap();
+    assert_eq!(utc.to_string(), "2027-01-01T00:00:00.000Z");
+}
+
+#[test]
+fn century_and_validation() {
+    let time = GPSTime { hour: 23, minute: 59, second: 60, millisecond: 999 };
+    let utc = UtcDateTime::from_gps(GPSDate { day: 31, month: 12, year: 99 }, time, None).unwrap();
+    assert_eq!(utc.year, 1999);
+    assert_eq!(utc.to_string(), "1999-12-31T23:59:60.999Z");
+    let utc = UtcDateTime::from_gps(GPSDate { day: 29, month: 2, year: 24 }, time, Some(2124)).unwrap();
+    assert_eq!(utc.year, 2124);
+    let utc = UtcDateTime::from_gps(GPSDate { day: 1, month: 1, year: 0 }, time, Some(2099)).unwrap();
+    assert_eq!(utc.year, 2100);
+
+    assert!(UtcDateTime::new(2021, 2, 29, 0, 0, 0, 0).is_none());
+    assert!(UtcDateTime::new(2000, 2, 29, 0, 0, 0, 0).is_some());
+    assert!(UtcDateTime::new(1900, 2, 29, 0, 0, 0, 0).is_none());
+    assert!(UtcDateTime::new(2021, 13, 1, 0, 0, 0, 0).is_none());
+    assert!(UtcDateTime::new(2021, 1, 0, 0, 0, 0, 0).is_none());
+    assert!(UtcDateTime::new(2021, 1, 1, 24, 0, 0, 0).is_none());
+    assert!(UtcDateTime::new(2021, 1, 1, 0, 0, 0, 1000).is_none());
+    // cold start: empty date and time
+    assert!(UtcDateTime::from_gps(GPSDate::new(), GPSTime::new(), None).is_none());
+}
+
+#[test]
+fn unix_time_round_trip() {
+    let epoch = UtcDateTime::from_unix_seconds(0).unwrap();
+    assert_eq!(epoch.to_string(), "1970-01-01T00:00:00.000Z");
+    assert_eq!(epoch.weekday(), Weekday::Thursday);
+    let before = UtcDateTime::from_unix_millis(-1).unwrap();
+    assert_eq!(before.to_string(), "1969-12-31T23:59:59.999Z");
+    assert_eq!(before.day_of_year(), 365);
+
+    let leap_day = UtcDateTime::new(2000, 2, 29, 0, 0, 0, 0).unwrap();
+    assert_eq!(leap_day.unix_seconds(), 951_782_400);
+    let last = UtcDateTime::new(9999, 12, 31, 23, 59, 59, 0).unwrap();
+    assert_eq!(last.unix_seconds(), 253_402_300_799);
+    assert_eq!(UtcDateTime::from_unix_seconds(253_402_300_800), None);
+
+    for millis in (-100_000_000_000i64..250_000_000_000_000).step_by(7_777_777_777_777) {
+        let utc = UtcDateTime::from_unix_millis(millis).unwrap();
+        assert_eq!(utc.unix_millis(), millis, "{}", utc);
+    }
+}
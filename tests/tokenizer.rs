@@ -0,0 +1,87 @@
+mod common;
+
+use common::*;
+use neo6::{Tokenizer, NmeaError, NEO6};
+
+fn tokenize(chunks: &[&[u8]], buf: &mut [u8]) -> Vec<Result<Vec<u8>, NmeaError>> {
+    let mut tokenizer = Tokenizer::new(buf);
+    let mut out = Vec::new();
+    for chunk in chunks {
+        let mut input = *chunk;
+        while let Some(token) = tokenizer.next(&mut input) {
+            out.push(token.map(|line| line.to_vec()));
+        }
+    }
+    out
+}
+
+fn lines(capture: &'static [u8]) -> Vec<Result<Vec<u8>, NmeaError>> {
+    sentences(capture).map(|s| Ok(s[..s.len() - 2].to_vec())).collect()
+}
+
+#[test]
+fn any_chunk_size_gives_the_same_sentences() {
+    for size in 1..=FIX_EPOCH.len() {
+        let chunks: Vec<&[u8]> = FIX_EPOCH.chunks(size).collect();
+        assert_eq!(tokenize(&chunks, &mut [0u8; 82]), lines(FIX_EPOCH), "chunk size {}", size);
+    }
+}
+
+#[test]
+fn sentence_inside_a_chunk_is_not_copied() {
+    let mut buf = [0u8; 82];
+    let mut tokenizer = Tokenizer::new(&mut buf);
+    let mut input = FIX_RMC;
+    let line = tokenizer.next(&mut input).unwrap().unwrap();
+    assert_eq!(line.as_ptr(), FIX_RMC.as_ptr());
+    // the '\n' is skipped on the next call
+    assert_eq!(input, b"\n");
+}
+
+#[test]
+fn carriage_return_only_and_garbage() {
+    let stream = [
+        &b"\xff\x00noise"[..],
+        b"$GPTXT,01,01,02,u-blox ag - www.u-blox.com*50\r",
+        b"$GPGGA,0835\x8059.00*5E\r\n",
+        b"$GPRMC,0835",
+        b"$GPTXT,01,01,02,u-blox ag - www.u-blox.com*51\n",
+        b"$GPTXT,01,01,02,u-blox ag - www.u-blox.com*50\r\n",
+    ];
+    let txt = b"$GPTXT,01,01,02,u-blox ag - www.u-blox.com*50".to_vec();
+    assert_eq!(tokenize(&stream, &mut [0u8; 82]), [
+        Ok(txt.clone()),
+        Err(NmeaError::BadFrame),
+        Err(NmeaError::BadFrame),
+        Err(NmeaError::ChecksumMismatch),
+        Ok(txt),
+    ]);
+}
+
+#[test]
+fn overlong_lines_are_reported() {
+    let mut long = b"$GPTXT,".to_vec();
+    long.extend_from_slice(&[b'x'; 100]);
+    long.extend_from_slice(b"*00\r\n");
+    let chunks: Vec<&[u8]> = long.chunks(16).chain([FIX_RMC]).collect();
+    let out = tokenize(&chunks, &mut [0u8; 82]);
+    assert_eq!(out[0], Err(NmeaError::LineTooLong));
+    assert_eq!(out[1], lines(FIX_RMC)[0]);
+
+    // one chunk, too long for the buffer even though it is not copied
+    assert_eq!(tokenize(&[&long], &mut [0u8; 82]), [Err(NmeaError::LineTooLong)]);
+}
+
+#[test]
+fn driver_takes_whole_chunks() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(FIX_EPOCH);
+    neo.feed(b"$GPRMC,0835");
+    neo.feed(COLD_START);
+    let stats = neo.statistics();
+    assert_eq!(stats.sentences, 8 + sentences(COLD_START).count() as u32);
+    assert_eq!(stats.parse_errors, 1);
+    neo.end_epoch();
+    assert!(!neo.data_valid());
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use common::*;
+use neo6::ubx::{self, class, nav, encode_frame, parse_message};
+use neo6::{New, NEO6, TapMode, UbxParser, UbxMessage, UbxError, GpsFix, FixType, FixMode, GPSTime};
+
+fn frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; payload.len() + ubx::FRAME_OVERHEAD];
+    let len = encode_frame(class, id, payload, &mut out).unwrap();
+    out.truncate(len);
+    out
+}
+
+fn posllh() -> Vec<u8> {
+    let mut p = Vec::new();
+    p.extend_from_slice(&123_000u32.to_le_bytes());
+    p.extend_from_slice(&199_031_038i32.to_le_bytes());
+    p.extend_from_slice(&501_078_420i32.to_le_bytes());
+    p.extend_from_slice(&269_500i32.to_le_bytes());
+    p.extend_from_slice(&229_400i32.to_le_bytes());
+    p.extend_from_slice(&2_500u32.to_le_bytes());
+    p.extend_from_slice(&4_000u32.to_le_bytes());
+    p
+}
+
+fn pvt() -> Vec<u8> {
+    let mut p = vec![0u8; 92];
+    p[4..6].copy_from_slice(&2021u16.to_le_bytes());
+    p[6..11].copy_from_slice(&[5, 18, 8, 35, 59]);
+    p[11] = 0x07;
+    p[20] = 3;
+    p[21] = 0x01;
+    p[23] = 9;
+    p[24..28].copy_from_slice(&199_031_038i32.to_le_bytes());
+    p[28..32].copy_from_slice(&(-501_078_420i32).to_le_bytes());
+    p[36..40].copy_from_slice(&229_400i32.to_le_bytes());
+    p[60..64].copy_from_slice(&1_000i32.to_le_bytes());
+    p[64..68].copy_from_slice(&5_470_000i32.to_le_bytes());
+    p[76..78].copy_from_slice(&186u16.to_le_bytes());
+    p
+}
+
+#[test]
+fn encodes_cfg_rate_poll() {
+    assert_eq!(frame(class::CFG, 0x08, &[]), [0xB5, 0x62, 0x06, 0x08, 0x00, 0x00, 0x0E, 0x30]);
+    let mut short = [0u8; 7];
+    assert_eq!(encode_frame(class::CFG, 0x08, &[], &mut short), Err(UbxError::BufferTooSmall));
+}
+
+#[test]
+fn parser_roundtrip_and_checksum() {
+    let mut parser = UbxParser::new();
+    let bytes = frame(class::ACK, ubx::ack::ACK, &[0x06, 0x08]);
+    let mut done = false;
+    for b in &bytes {
+        done = parser.push(*b).unwrap();
+    }
+    assert!(done);
+    let (c, i, p) = parser.frame().unwrap();
+    assert_eq!(parse_message(c, i, p), Ok(UbxMessage::AckAck(0x06, 0x08)));
+    parser.clear();
+
+    let mut bad = bytes.clone();
+    *bad.last_mut().unwrap() ^= 0xFF;
+    let result: Vec<_> = bad.iter().map(|b| parser.push(*b)).collect();
+    assert_eq!(result.last(), Some(&Err(UbxError::ChecksumMismatch)));
+    assert!(parser.frame().is_none());
+}
+
+#[test]
+fn decodes_nav_messages() {
+    match parse_message(class::NAV, nav::POSLLH, &posllh()).unwrap() {
+        UbxMessage::NavPosllh(m) => {
+            assert_eq!(m.lat, 501_078_420);
+            assert_eq!(m.height_msl, 229_400);
+            assert_eq!(m.v_acc, 4_000);
+        },
+        other => panic!("{:?}", other),
+    }
+    let mut status = vec![0u8; 16];
+    status[4] = 2;
+    status[5] = 0x03;
+    match parse_message(class::NAV, nav::STATUS, &status).unwrap() {
+        UbxMessage::NavStatus(m) => {
+            assert_eq!(m.fix, GpsFix::Fix2D);
+            assert_eq!(m.fix.fix_type(m.flags), FixType::DifferentialFix);
+            assert_eq!(m.fix.fix_mode(), FixMode::D2);
+        },
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(parse_message(class::NAV, nav::POSLLH, &[0; 27]), Err(UbxError::BadLength));
+    assert_eq!(parse_message(0x0A, 0x04, &[]), Err(UbxError::UnknownMessage));
+}
+
+#[test]
+fn mixed_stream_over_mock_serial() {
+    let mut stream = Vec::new();
+    stream.extend_from_slice(FIX_GSA);
+    // payload with '$' and '\n' must not disturb the NMEA framer
+    let mut payload = posllh();
+    payload[0] = b'$';
+    payload[1] = b'\n';
+    stream.extend(frame(class::NAV, nav::POSLLH, &payload));
+    stream.extend_from_slice(FIX_RMC);
+    stream.extend(frame(class::NAV, nav::PVT, &pvt()));
+
+    let mut buf = [0u8; 200];
+    let mut tap = [0u8; 256];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(&stream), MockTx::default()).with_log(MockTx::default());
+    neo.set_tap(TapMode::Raw, &mut tap);
+    while !neo.rx_mut().is_done() {
+        neo.receive();
+        neo.write_tap();
+    }
+    neo.write_tap();
+    neo.end_epoch();
+
+    let stats = neo.statistics();
+    assert_eq!(stats.sentences, 2);
+    assert_eq!(stats.ubx_frames, 2);
+    assert_eq!(stats.errors(), 0);
+    let data = neo.get_data();
+    assert!(neo.data_valid());
+    assert_eq!(data.get_time(), GPSTime { hour: 8, minute: 35, second: 59, millisecond: 0 });
+    assert_eq!(data.get_full_year(), Some(2021));
+    assert_eq!(data.satellites_no(), 9);
+    assert_eq!(data.get_fix_mode(), FixMode::D3);
+    let position = data.get_position();
+    assert_eq!(position.latitude.degrees_e7(), -501_078_420);
+    assert_eq!(position.longitude.degrees_e7(), 199_031_038);
+    assert_eq!(position.altitude.to_string(), "229.400");
+    assert_eq!(data.get_speed_kmh().to_string(), "3.600");
+    assert_eq!(data.get_course().to_string(), "54.70000");
+    // only NMEA goes to the tap
+    assert_eq!(neo.log_mut().unwrap().written, [FIX_GSA, FIX_RMC].concat());
+}
+
+#[test]
+fn frames_not_kept_are_skipped_by_length() {
+    // a payload that would pass for NMEA if it reached the tokenizer
+    let mut long = FIX_RMC.to_vec();
+    long.resize(200, b' ');
+    let mut stream = frame(class::MON, 0x04, &long);
+    stream.extend_from_slice(FIX_GGA);
+
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(&stream);
+
+    let stats = neo.statistics();
+    assert_eq!(stats.sentences, 1);
+    assert_eq!(stats.last_ubx_error, Some(UbxError::PayloadTooLong));
+    assert_eq!(neo.get_pending().get_time(), GPSTime { hour: 8, minute: 35, second: 59, millisecond: 0 });
+}
+
+#[test]
+fn false_syncs_lose_no_sentences() {
+    // a stray sync char, then a header of no known class with a huge length
+    let mut stream = vec![0xB5];
+    stream.extend_from_slice(FIX_RMC);
+    stream.extend_from_slice(&[0xB5, 0x62, 0x77, 0x01, 0xFF, 0xFF]);
+    for _ in 0..50 {
+        stream.extend_from_slice(FIX_EPOCH);
+    }
+
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(&stream);
+
+    let stats = neo.statistics();
+    assert_eq!(stats.sentences, 1 + 50 * 8);
+    assert_eq!(stats.ubx_errors, 2);
+    assert_eq!(stats.last_ubx_error, Some(UbxError::FalseSync));
+
+    let mut parser = UbxParser::new();
+    assert_eq!(parser.push(0xB5), Ok(false));
+    assert_eq!(parser.push(0xB5), Err(UbxError::FalseSync));
+    assert!(!parser.in_frame());
+}
+
+#[test]
+fn sync_char_ends_a_broken_sentence() {
+    let mut stream = FIX_RMC[..30].to_vec();
+    stream.extend(frame(class::NAV, nav::POSLLH, &posllh()));
+    stream.extend_from_slice(FIX_GSA);
+
+    let mut buf = [0u8; 200];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(&stream);
+
+    let stats = neo.statistics();
+    assert_eq!(stats.parse_errors, 1);
+    assert_eq!(stats.last_error, Some(neo6::NmeaError::BadFrame));
+    assert_eq!(stats.ubx_frames, 1);
+    assert_eq!(stats.sentences, 1);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::*;
+use neo6::{NEO6, Decimal, Group, Validity};
+
+#[test]
+fn fix_epoch_is_fresh() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(FIX_EPOCH);
+
+    assert!(neo.data_valid());
+    assert_eq!(neo.validity(Decimal::new(2, 0)), Validity::Fresh);
+    // HDOP is 1.01
+    assert_eq!(neo.validity(Decimal::new(1, 0)), Validity::Degraded);
+    let data = neo.get_data();
+    assert_eq!(data.get_epoch(), 1);
+    for group in [Group::Fix, Group::Position, Group::Velocity, Group::Dop] {
+        assert_eq!(data.get_stamp(group).unwrap().epoch, 1);
+        assert_eq!(data.age(group), Some(0));
+    }
+}
+
+#[test]
+fn fix_goes_stale_without_updates() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(FIX_EPOCH);
+    // only the time moves on, three seconds later
+    neo.feed(&nmea("GPZDA,083602.00,18,05,2021,00,00"));
+    neo.end_epoch();
+
+    assert!(!neo.data_valid());
+    assert_eq!(neo.validity(Decimal::new(2, 0)), Validity::Stale);
+    assert_eq!(neo.get_data().age(Group::Position), Some(3));
+    neo.set_max_age(3);
+    assert_eq!(neo.validity(Decimal::new(2, 0)), Validity::Fresh);
+}
+
+#[test]
+fn lost_fix_keeps_last_position() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.feed(FIX_EPOCH);
+    let position = neo.get_data().get_position();
+    neo.feed(&nmea("GPRMC,083600.00,V,,,,,,,180521,,,N"));
+    neo.end_epoch();
+
+    assert_eq!(neo.validity(Decimal::new(2, 0)), Validity::NoFix);
+    let data = neo.get_data();
+    assert_eq!(data.get_position(), position);
+    assert_eq!(data.age(Group::Position), Some(1));
+    assert_eq!(data.age(Group::Fix), Some(0));
+}
+
+#[test]
+fn old_dop_degrades_fix() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.set_max_age(1);
+    neo.feed(FIX_EPOCH);
+    // RMC keeps the fix and position current, the GSA is two seconds old
+    neo.feed(RMC_ONLY);
+    neo.end_epoch();
+
+    assert!(neo.data_valid());
+    assert_eq!(neo.get_data().get_epoch(), 3);
+    assert_eq!(neo.get_data().age(Group::Dop), Some(2));
+    assert_eq!(neo.validity(Decimal::new(2, 0)), Validity::Degraded);
+}
+
+#[test]
+fn silent_receiver_goes_stale() {
+    let max_hdop = Decimal::new(2, 0);
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    // received before the first tick: counts as received at it
+    neo.feed(FIX_EPOCH);
+    neo.tick(1000);
+    assert_eq!(neo.validity(max_hdop), Validity::Fresh);
+    assert_eq!(neo.get_data().get_received(), Some(1000));
+
+    // then nothing more arrives
+    neo.tick(3000);
+    assert!(neo.data_valid());
+    neo.tick(3001);
+    assert!(!neo.data_valid());
+    assert_eq!(neo.validity(max_hdop), Validity::Stale);
+    assert_eq!(neo.get_data().silence(3001), Some(2001));
+    // the receiver data itself is consistent
+    assert_eq!(neo.get_data().validity(max_hdop), Validity::Fresh);
+
+    neo.set_max_silence(5000);
+    assert!(neo.data_valid());
+}
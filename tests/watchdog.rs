@@ -0,0 +1,67 @@
+mod common;
+
+use common::*;
+use neo6::{New, NEO6, Liveness, Recovery, StartMode};
+
+#[test]
+fn liveness_follows_the_stream() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    assert_eq!(neo.tick(0), None);
+    assert_eq!(neo.liveness(), Liveness::NotDetected);
+
+    // wrong baud rate: bytes, but nothing valid
+    neo.feed(&[0xF0; 16]);
+    neo.tick(100);
+    assert_eq!(neo.liveness(), Liveness::NotDetected);
+    assert_eq!(neo.watchdog().last_byte(), Some(100));
+    assert_eq!(neo.watchdog().last_valid(), None);
+
+    neo.feed(COLD_START);
+    neo.tick(200);
+    assert_eq!(neo.liveness(), Liveness::Streaming);
+    neo.feed(FIX_EPOCH);
+    neo.tick(300);
+    assert_eq!(neo.liveness(), Liveness::Fix);
+
+    neo.tick(2300);
+    assert_eq!(neo.liveness(), Liveness::Fix);
+    neo.tick(2301);
+    assert_eq!(neo.liveness(), Liveness::Silent);
+    assert_eq!(neo.watchdog().last_valid(), Some(300));
+}
+
+#[test]
+fn recovery_is_repeated_while_silent() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    neo.watchdog_mut().set_recovery(Recovery::Reprobe, 5000);
+    // the tick count wraps on the way
+    let start = u32::MAX - 3000;
+    let at = |ticks: u32| start.wrapping_add(ticks);
+
+    assert_eq!(neo.tick(at(0)), None);
+    assert_eq!(neo.tick(at(4999)), None);
+    assert_eq!(neo.tick(at(5000)), Some(Recovery::Reprobe));
+    assert_eq!(neo.tick(at(9999)), None);
+    assert_eq!(neo.tick(at(10_000)), Some(Recovery::Reprobe));
+
+    neo.feed(FIX_EPOCH);
+    assert_eq!(neo.tick(at(10_500)), None);
+    // silent again: timeout plus the recovery delay
+    assert_eq!(neo.tick(at(15_499)), None);
+    assert_eq!(neo.tick(at(15_500)), Some(Recovery::Reprobe));
+}
+
+#[test]
+fn reset_sends_cfg_rst() {
+    let mut buf = [0u8; 82];
+    let mut neo = NEO6::new(&mut buf, MockRx::new(&[]), MockTx::default());
+    neo.reset_receiver(StartMode::Cold).unwrap();
+    let sent = &neo.tx_mut().unwrap().written;
+    assert_eq!(sent.len(), 12);
+    assert_eq!(sent[..10], [0xB5, 0x62, 0x06, 0x04, 0x04, 0x00, 0xFF, 0xFF, 0x01, 0x00]);
+
+    let mut neo = NEO6::receive_only(&mut buf, MockRx::new(&[]));
+    assert!(neo.reset_receiver(StartMode::Hot).is_err());
+}